@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+use core_ds::account::{Entity, MaxSize};
+use core_ds::error::ComponentError;
+use core_ds::state::SerializedComponent;
+
+/// Serializes a component, reserving its max size on the Entity
+pub fn serialize_component<T: AnchorSerialize + MaxSize>(component: &T) -> Result<SerializedComponent> {
+    let data = component.try_to_vec()?;
+    if data.len() as u64 > T::get_max_size() {
+        return err!(ComponentError::InvalidDataLengthError);
+    }
+
+    Ok(SerializedComponent {
+        max_size: T::get_max_size(),
+        data
+    })
+}
+
+/// Deserializes a component off the Entity, failing with ComponentNotFound if the Entity doesn't have it
+pub fn get_component<T: AnchorDeserialize>(entity: &Entity, component: &Pubkey) -> Result<T> {
+    match entity.components.get(component) {
+        Some(serialized) => Ok(T::try_from_slice(&serialized.data)?),
+        None => err!(ComponentError::ComponentNotFound),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Debug, PartialEq)]
+    struct Name {
+        name: String,
+    }
+
+    impl MaxSize for Name {
+        fn get_max_size() -> u64 {
+            return 4 + 8;
+        }
+    }
+
+    fn entity(components: Vec<(Pubkey, SerializedComponent)>) -> Entity {
+        Entity {
+            version: core_ds::constant::ENTITY_LAYOUT_VERSION,
+            entity_id: 0,
+            instance: 0,
+            registry: Pubkey::new_unique(),
+            revision: 0,
+            components: components.into_iter().collect(),
+            component_revisions: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn serializes_with_max_size() {
+        let serialized = serialize_component(&Name { name: "arc".to_string() }).unwrap();
+        assert_eq!(serialized.max_size, Name::get_max_size());
+        assert_eq!(serialized.data, Name { name: "arc".to_string() }.try_to_vec().unwrap());
+    }
+
+    #[test]
+    fn rejects_components_over_max_size() {
+        let res = serialize_component(&Name { name: "longer than eight".to_string() });
+        assert_eq!(res.unwrap_err(), error!(ComponentError::InvalidDataLengthError));
+    }
+
+    #[test]
+    fn round_trips_components() {
+        let key = Pubkey::new_unique();
+        let entity = entity(vec![(key, serialize_component(&Name { name: "arc".to_string() }).unwrap())]);
+
+        assert_eq!(get_component::<Name>(&entity, &key).unwrap(), Name { name: "arc".to_string() });
+        assert_eq!(get_component::<Name>(&entity, &Pubkey::new_unique()).unwrap_err(), error!(ComponentError::ComponentNotFound));
+    }
+
+    #[test]
+    fn rejects_malformed_data() {
+        let key = Pubkey::new_unique();
+        let entity = entity(vec![(key, SerializedComponent { max_size: 12, data: vec![9, 0, 0, 0, 1] })]);
+
+        assert!(get_component::<Name>(&entity, &key).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+use anchor_lang::prelude::*;
+use std::collections::BTreeMap;
+use core_ds::state::{SerializedComponent, ComponentOperation};
+
+/**
+ * Accounts every Action Bundle passes along to the Registry, plus the bundle's PDA signer.
+ * Each call signs as the bundle and goes through the Registry, which checks the bundle's registration.
+ */
+pub struct BundleContext<'a, 'info> {
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+    pub registry_config: AccountInfo<'info>,
+    pub registry_program: AccountInfo<'info>,
+    pub core_ds: AccountInfo<'info>,
+    // CoreDS records every component change through the Noop program
+    pub noop_program: AccountInfo<'info>,
+    pub action_bundle: AccountInfo<'info>,
+    pub action_bundle_registration: AccountInfo<'info>,
+    // Action Bundle signer is the PDA [seed] with this bump
+    pub seed: &'a [u8],
+    pub bump: u8,
+}
+
+impl<'a, 'info> BundleContext<'a, 'info> {
+    pub fn create_entity(&self, registry_instance: AccountInfo<'info>, entity: AccountInfo<'info>, entity_id: u64, components: BTreeMap<Pubkey, SerializedComponent>) -> Result<()> {
+        let accounts = registry::cpi::accounts::InitEntity {
+            payer: self.payer.clone(),
+            system_program: self.system_program.clone(),
+            registry_config: self.registry_config.clone(),
+            entity,
+            registry_instance,
+            action_bundle: self.action_bundle.clone(),
+            action_bundle_registration: self.action_bundle_registration.clone(),
+            core_ds: self.core_ds.clone(),
+            noop_program: self.noop_program.clone(),
+        };
+
+        registry::cpi::init_entity(CpiContext::new_with_signer(
+            self.registry_program.clone(),
+            accounts,
+            crate::bundle_signer_seeds!(self.seed, self.bump)
+        ), entity_id, components)
+    }
+
+    /// Overrides replace the prefab's default data for components it already has
+    pub fn create_entity_from_prefab(&self, registry_instance: AccountInfo<'info>, entity: AccountInfo<'info>, prefab: AccountInfo<'info>, entity_id: u64, overrides: Vec<(Pubkey, Vec<u8>)>) -> Result<()> {
+        let accounts = registry::cpi::accounts::InitEntityFromPrefab {
+            payer: self.payer.clone(),
+            system_program: self.system_program.clone(),
+            registry_config: self.registry_config.clone(),
+            entity,
+            registry_instance,
+            prefab,
+            action_bundle: self.action_bundle.clone(),
+            action_bundle_registration: self.action_bundle_registration.clone(),
+            core_ds: self.core_ds.clone(),
+            noop_program: self.noop_program.clone(),
+        };
+
+        registry::cpi::init_entity_from_prefab(CpiContext::new_with_signer(
+            self.registry_program.clone(),
+            accounts,
+            crate::bundle_signer_seeds!(self.seed, self.bump)
+        ), entity_id, overrides)
+    }
+
+    /// Filter limits which components get copied, None copies all of them
+    pub fn clone_entity(&self, registry_instance: AccountInfo<'info>, source: AccountInfo<'info>, entity: AccountInfo<'info>, entity_id: u64, filter: Option<Vec<Pubkey>>) -> Result<()> {
+        let accounts = registry::cpi::accounts::CloneEntity {
+            payer: self.payer.clone(),
+            system_program: self.system_program.clone(),
+            registry_config: self.registry_config.clone(),
+            source,
+            entity,
+            registry_instance,
+            action_bundle: self.action_bundle.clone(),
+            action_bundle_registration: self.action_bundle_registration.clone(),
+            core_ds: self.core_ds.clone(),
+            noop_program: self.noop_program.clone(),
+        };
+
+        registry::cpi::clone_entity(CpiContext::new_with_signer(
+            self.registry_program.clone(),
+            accounts,
+            crate::bundle_signer_seeds!(self.seed, self.bump)
+        ), entity_id, filter)
+    }
+
+    pub fn add(&self, entity: AccountInfo<'info>, components: Vec<(Pubkey, SerializedComponent)>) -> Result<()> {
+        let accounts = registry::cpi::accounts::AddComponents {
+            payer: self.payer.clone(),
+            system_program: self.system_program.clone(),
+            registry_config: self.registry_config.clone(),
+            entity,
+            action_bundle: self.action_bundle.clone(),
+            action_bundle_registration: self.action_bundle_registration.clone(),
+            core_ds: self.core_ds.clone(),
+            noop_program: self.noop_program.clone(),
+        };
+
+        add_components(self.registry_program.clone(), accounts, self.seed, self.bump, components)
+    }
+
+    pub fn modify(&self, entity: AccountInfo<'info>, components: Vec<(Pubkey, Vec<u8>)>) -> Result<()> {
+        let accounts = registry::cpi::accounts::ModifyComponent {
+            registry_config: self.registry_config.clone(),
+            entity,
+            action_bundle: self.action_bundle.clone(),
+            action_bundle_registration: self.action_bundle_registration.clone(),
+            core_ds: self.core_ds.clone(),
+            noop_program: self.noop_program.clone(),
+        };
+
+        modify_components(self.registry_program.clone(), accounts, self.seed, self.bump, components)
+    }
+
+    /// Operations are applied in order, with a single realloc for the net change
+    pub fn mutate(&self, entity: AccountInfo<'info>, operations: Vec<ComponentOperation>) -> Result<()> {
+        let accounts = registry::cpi::accounts::MutateComponents {
+            payer: self.payer.clone(),
+            system_program: self.system_program.clone(),
+            registry_config: self.registry_config.clone(),
+            entity,
+            action_bundle: self.action_bundle.clone(),
+            action_bundle_registration: self.action_bundle_registration.clone(),
+            core_ds: self.core_ds.clone(),
+            noop_program: self.noop_program.clone(),
+        };
+
+        registry::cpi::req_mutate_components(CpiContext::new_with_signer(
+            self.registry_program.clone(),
+            accounts,
+            crate::bundle_signer_seeds!(self.seed, self.bump)
+        ), operations, None)
+    }
+
+    /// changes[i] is applied to entities[i], all in a single Registry call
+    pub fn batch_modify(&self, entities: Vec<AccountInfo<'info>>, changes: Vec<Vec<(Pubkey, Vec<u8>)>>) -> Result<()> {
+        let accounts = registry::cpi::accounts::BatchEntities {
+            payer: self.payer.clone(),
+            system_program: self.system_program.clone(),
+            registry_config: self.registry_config.clone(),
+            action_bundle: self.action_bundle.clone(),
+            action_bundle_registration: self.action_bundle_registration.clone(),
+            core_ds: self.core_ds.clone(),
+            noop_program: self.noop_program.clone(),
+        };
+
+        registry::cpi::req_batch_modify_components(CpiContext::new_with_signer(
+            self.registry_program.clone(),
+            accounts,
+            crate::bundle_signer_seeds!(self.seed, self.bump)
+        ).with_remaining_accounts(entities), changes, None)
+    }
+
+    /// Rent freed by the removed components goes back to the payer
+    pub fn remove(&self, entity: AccountInfo<'info>, components: Vec<Pubkey>) -> Result<()> {
+        let accounts = registry::cpi::accounts::RemoveComponent {
+            benefactor: self.payer.clone(),
+            system_program: self.system_program.clone(),
+            registry_config: self.registry_config.clone(),
+            entity,
+            action_bundle: self.action_bundle.clone(),
+            action_bundle_registration: self.action_bundle_registration.clone(),
+            core_ds: self.core_ds.clone(),
+            noop_program: self.noop_program.clone(),
+        };
+
+        registry::cpi::req_remove_component(CpiContext::new_with_signer(
+            self.registry_program.clone(),
+            accounts,
+            crate::bundle_signer_seeds!(self.seed, self.bump)
+        ), components, None)
+    }
+
+    /// Entity has to be empty, not entangled with an ARCNFT and have its relations closed. Rent goes back to the payer
+    pub fn remove_entity(&self, entity: AccountInfo<'info>, entity_arcnft: AccountInfo<'info>, entity_relations: AccountInfo<'info>) -> Result<()> {
+        let accounts = registry::cpi::accounts::RemoveEntity {
+            benefactor: self.payer.clone(),
+            system_program: self.system_program.clone(),
+            registry_config: self.registry_config.clone(),
+            entity,
+            entity_arcnft,
+            entity_relations,
+            action_bundle: self.action_bundle.clone(),
+            action_bundle_registration: self.action_bundle_registration.clone(),
+            core_ds: self.core_ds.clone(),
+        };
+
+        registry::cpi::req_remove_entity(CpiContext::new_with_signer(
+            self.registry_program.clone(),
+            accounts,
+            crate::bundle_signer_seeds!(self.seed, self.bump)
+        ), None)
+    }
+
+    /// Entangles the Entity with an NFT mint. The mint's authority has to be revoked or held by the bundle signer
+    pub fn mint_nft(&self, registry_instance: AccountInfo<'info>, entity: AccountInfo<'info>, mint: AccountInfo<'info>, arcnft: AccountInfo<'info>, entity_arcnft: AccountInfo<'info>) -> Result<()> {
+        let accounts = registry::cpi::accounts::MintARCNFT {
+            payer: self.payer.clone(),
+            system_program: self.system_program.clone(),
+            registry_config: self.registry_config.clone(),
+            entity,
+            mint,
+            arcnft,
+            entity_arcnft,
+            registry_instance,
+            action_bundle: self.action_bundle.clone(),
+            action_bundle_registration: self.action_bundle_registration.clone(),
+            core_ds: self.core_ds.clone(),
+        };
+
+        registry::cpi::mint_arcnft(CpiContext::new_with_signer(
+            self.registry_program.clone(),
+            accounts,
+            crate::bundle_signer_seeds!(self.seed, self.bump)
+        ))
+    }
+
+    /// Mint's supply has to be burned already. Rent goes back to the payer
+    pub fn burn_nft(&self, entity: AccountInfo<'info>, mint: AccountInfo<'info>, arcnft: AccountInfo<'info>, entity_arcnft: AccountInfo<'info>) -> Result<()> {
+        let accounts = registry::cpi::accounts::BurnARCNFT {
+            benefactor: self.payer.clone(),
+            system_program: self.system_program.clone(),
+            registry_config: self.registry_config.clone(),
+            entity,
+            mint,
+            arcnft,
+            entity_arcnft,
+            action_bundle: self.action_bundle.clone(),
+            action_bundle_registration: self.action_bundle_registration.clone(),
+            core_ds: self.core_ds.clone(),
+        };
+
+        registry::cpi::burn_arcnft(CpiContext::new_with_signer(
+            self.registry_program.clone(),
+            accounts,
+            crate::bundle_signer_seeds!(self.seed, self.bump)
+        ))
+    }
+}
+
+/// Adds components signing as the bundle PDA [seed] with this bump, for accounts structs BundleContext doesn't fit
+pub fn add_components<'info>(registry_program: AccountInfo<'info>, accounts: registry::cpi::accounts::AddComponents<'info>, seed: &[u8], bump: u8, components: Vec<(Pubkey, SerializedComponent)>) -> Result<()> {
+    registry::cpi::req_add_component(CpiContext::new_with_signer(
+        registry_program,
+        accounts,
+        crate::bundle_signer_seeds!(seed, bump)
+    ), components, None)
+}
+
+/// Modifies components signing as the bundle PDA [seed] with this bump. Needs no payer, so it fits instructions that only sign as an authority
+pub fn modify_components<'info>(registry_program: AccountInfo<'info>, accounts: registry::cpi::accounts::ModifyComponent<'info>, seed: &[u8], bump: u8, components: Vec<(Pubkey, Vec<u8>)>) -> Result<()> {
+    registry::cpi::req_modify_component(CpiContext::new_with_signer(
+        registry_program,
+        accounts,
+        crate::bundle_signer_seeds!(seed, bump)
+    ), components, None)
+}
@@ -0,0 +1,72 @@
+//! Boilerplate every Action Bundle repeats: a PDA signer, CPIs through the Registry into Core DS,
+//! and (de)serializing components on Entities.
+
+// Same as the programs: Anchor's Error is large by design, and explicit returns are the house style
+#![allow(clippy::result_large_err)]
+#![allow(clippy::needless_return)]
+
+pub mod component;
+pub mod context;
+
+pub use component::*;
+pub use context::*;
+
+/**
+ * Signer seeds for an Action Bundle PDA, for use inline in a CPI call:
+ * `CpiContext::new_with_signer(program, accounts, bundle_signer_seeds!(b"my_signer", bump))`
+ */
+#[macro_export]
+macro_rules! bundle_signer_seeds {
+    ($seed:expr, $bump:expr) => {
+        &[&[$seed.as_ref(), &[$bump]][..]]
+    };
+}
+
+/**
+ * Builds a BundleContext from an Anchor Context. The accounts struct needs `payer`, `system_program`,
+ * `registry_config`, `registry_program`, `core_ds_program`, `noop_program`, the signer PDA (with a `bump`) and the
+ * bundle's registration, which defaults to `action_bundle_registration`.
+ * Instructions where someone else pays or gets the rent back can name that account in place of `payer`.
+ */
+#[macro_export]
+macro_rules! bundle_context {
+    ($ctx:expr, $signer:ident, $seed:expr) => {
+        $crate::bundle_context!($ctx, $signer, action_bundle_registration, $seed)
+    };
+    ($ctx:expr, $signer:ident, $registration:ident, $seed:expr) => {
+        $crate::bundle_context!($ctx, $signer, $registration, $seed, payer)
+    };
+    ($ctx:expr, $signer:ident, $registration:ident, $seed:expr, $payer:ident) => {
+        $crate::BundleContext {
+            payer: $ctx.accounts.$payer.to_account_info(),
+            system_program: $ctx.accounts.system_program.to_account_info(),
+            registry_config: $ctx.accounts.registry_config.to_account_info(),
+            registry_program: $ctx.accounts.registry_program.to_account_info(),
+            core_ds: $ctx.accounts.core_ds_program.to_account_info(),
+            noop_program: $ctx.accounts.noop_program.to_account_info(),
+            action_bundle: $ctx.accounts.$signer.to_account_info(),
+            action_bundle_registration: $ctx.accounts.$registration.to_account_info(),
+            seed: $seed,
+            bump: *$ctx.bumps.get(stringify!($signer)).unwrap(),
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::prelude::Pubkey;
+
+    #[test]
+    fn signer_seeds_sign_for_the_bundle_pda() {
+        let program_id = Pubkey::new_unique();
+        let (signer, bump) = Pubkey::find_program_address(&[b"tsab_signer"], &program_id);
+
+        // Only usable inline, like in a CPI call
+        assert_eq!(bundle_signer_seeds!(b"tsab_signer", bump).len(), 1);
+        assert_eq!(Pubkey::create_program_address(bundle_signer_seeds!(b"tsab_signer", bump)[0], &program_id).unwrap(), signer);
+
+        // Seeds held as a slice, the way BundleContext stores them
+        let seed: &[u8] = b"tsab_signer";
+        assert_eq!(Pubkey::create_program_address(bundle_signer_seeds!(seed, bump)[0], &program_id).unwrap(), signer);
+    }
+}
@@ -0,0 +1,438 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{system_program, Id, InstructionData, ToAccountMetas};
+use core_ds::account::Entity;
+use core_ds::constant::{SEEDS_ENTITY_PREFIX, SEEDS_REGISTRYINSTANCE_PREFIX};
+use core_ds::context::apply_component_operations;
+use core_ds::state::{ComponentOperation, SerializedComponent};
+use registry::state::ComponentAccess;
+use solana_sdk::hash::Hash;
+use spl_account_compression::Noop;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::transaction::Transaction;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::component::fetch_entity;
+use crate::error::*;
+use crate::fetcher::*;
+
+pub fn get_registry_instance_address(instance: u64) -> Pubkey {
+    return Pubkey::find_program_address(
+        &[
+            SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry::ID.as_ref(),
+            instance.to_be_bytes().as_ref()
+        ],
+        &core_ds::ID
+    ).0;
+}
+
+pub fn get_entity_address(instance: u64, entity_id: u64) -> Pubkey {
+    return Pubkey::find_program_address(
+        &[
+            SEEDS_ENTITY_PREFIX,
+            entity_id.to_be_bytes().as_ref(),
+            get_registry_instance_address(instance).as_ref()
+        ],
+        &core_ds::ID
+    ).0;
+}
+
+pub fn get_registry_config_address() -> Pubkey {
+    return Pubkey::find_program_address(&[b"registry_signer"], &registry::ID).0;
+}
+
+pub fn get_instance_authority_address(registry_instance: &Pubkey) -> Pubkey {
+    return Pubkey::find_program_address(
+        &[
+            b"instance_authority",
+            registry_instance.as_ref()
+        ],
+        &registry::ID
+    ).0;
+}
+
+pub fn get_manifest_address(action_bundle: &Pubkey) -> Pubkey {
+    return Pubkey::find_program_address(
+        &[
+            b"action_bundle_manifest",
+            action_bundle.as_ref()
+        ],
+        &registry::ID
+    ).0;
+}
+
+pub fn get_registration_address(registry_instance: &Pubkey, action_bundle: &Pubkey) -> Pubkey {
+    return Pubkey::find_program_address(
+        &[
+            b"action_bundle_registration",
+            registry_instance.as_ref(),
+            action_bundle.as_ref()
+        ],
+        &registry::ID
+    ).0;
+}
+
+/**
+ * The Instance authority's half of registering an Action Bundle: approves what the bundle
+ * published in its manifest for one Instance. Signed by the payer and the authority, not the bundle.
+ */
+pub fn register_from_manifest_instruction(payer: &Pubkey, authority: &Pubkey, action_bundle: &Pubkey, instance: u64) -> Instruction {
+    let registry_instance = get_registry_instance_address(instance);
+    return Instruction {
+        program_id: registry::ID,
+        accounts: registry::accounts::RegisterFromManifest {
+            payer: *payer,
+            system_program: system_program::ID,
+            registry_instance,
+            instance_authority: get_instance_authority_address(&registry_instance),
+            authority: *authority,
+            action_bundle_manifest: get_manifest_address(action_bundle),
+            action_bundle_registration: get_registration_address(&registry_instance, action_bundle),
+            action_bundle: *action_bundle,
+        }.to_account_metas(None),
+        data: registry::instruction::RegisterActionBundleFromManifest {}.data(),
+    };
+}
+
+/**
+ * An Action Bundle that lives off chain, e.g. on a game server: a keypair signs as `action_bundle`
+ * in the Registry's req_* instructions, so any Entity change the server computes goes through the Registry's checks.
+ */
+pub struct OffchainBundle {
+    pub keypair: Keypair,
+}
+
+impl OffchainBundle {
+    pub fn new(keypair: Keypair) -> Self {
+        return OffchainBundle { keypair };
+    }
+
+    pub fn address(&self) -> Pubkey {
+        return self.keypair.pubkey();
+    }
+
+    /**
+     * The bundle's half of registering: the components it reads and writes and the Instances it wants.
+     * Signed by the payer and the bundle keypair, each Instance's authority then calls register_from_manifest_instruction.
+     */
+    pub fn publish_manifest_instruction(&self, payer: &Pubkey, components: Vec<(Pubkey, ComponentAccess)>, can_mint: bool, instances: Vec<u64>) -> Instruction {
+        return Instruction {
+            program_id: registry::ID,
+            accounts: registry::accounts::PublishManifest {
+                payer: *payer,
+                system_program: system_program::ID,
+                action_bundle_manifest: get_manifest_address(&self.address()),
+                action_bundle: self.address(),
+            }.to_account_metas(None),
+            data: registry::instruction::PublishManifest { components, can_mint, instances }.data(),
+        };
+    }
+
+    /**
+     * req_mutate_components for the Entity as it was fetched. The Entity's revision is passed along,
+     * so the transaction fails with StaleEntity instead of overwriting a change the server hasn't seen.
+     */
+    pub fn mutate_instruction(&self, payer: &Pubkey, entity_address: &Pubkey, entity: &Entity, operations: Vec<ComponentOperation>) -> Instruction {
+        let registry_instance = get_registry_instance_address(entity.instance);
+        return Instruction {
+            program_id: registry::ID,
+            accounts: registry::accounts::MutateComponents {
+                payer: *payer,
+                system_program: system_program::ID,
+                registry_config: get_registry_config_address(),
+                entity: *entity_address,
+                action_bundle: self.address(),
+                action_bundle_registration: get_registration_address(&registry_instance, &self.address()),
+                core_ds: core_ds::ID,
+                noop_program: Noop::id(),
+            }.to_account_metas(None),
+            data: registry::instruction::ReqMutateComponents {
+                operations,
+                expected_revision: Some(entity.revision)
+            }.data(),
+        };
+    }
+
+    // The payer can be the bundle keypair itself
+    pub fn sign(&self, payer: &Keypair, instructions: &[Instruction], recent_blockhash: Hash) -> Transaction {
+        let signers: Vec<&Keypair> = if payer.pubkey() == self.address() {
+            vec![payer]
+        } else {
+            vec![payer, &self.keypair]
+        };
+        return Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &signers,
+            recent_blockhash
+        );
+    }
+
+    /**
+     * Fetches the Entity, computes the mutation's operations from it and signs a transaction applying them.
+     * Keep the returned Entity and operations to verify_mutation against once the transaction lands.
+     */
+    pub fn prepare(&self, fetcher: &impl AccountFetcher, payer: &Keypair, mutation: &QueuedMutation, recent_blockhash: Hash) -> Result<(Entity, Vec<ComponentOperation>, Transaction)> {
+        let entity = fetch_entity(fetcher, &mutation.entity)?;
+        let operations = (mutation.compute)(&entity);
+        let ix = self.mutate_instruction(&payer.pubkey(), &mutation.entity, &entity, operations.clone());
+        return Ok((entity, operations, self.sign(payer, &[ix], recent_blockhash)));
+    }
+}
+
+// Operations for a mutation, from the Entity as it was just fetched
+pub type ComputeOperations = Arc<dyn Fn(&Entity) -> Vec<ComponentOperation> + Send + Sync>;
+
+/**
+ * A change to one Entity. Operations hold absolute component bytes, so they're computed from the Entity
+ * every time the mutation is prepared, and a retry builds on whatever changed since instead of overwriting it.
+ */
+#[derive(Clone)]
+pub struct QueuedMutation {
+    pub entity: Pubkey,
+    pub compute: ComputeOperations,
+}
+
+impl QueuedMutation {
+    pub fn new(entity: Pubkey, compute: impl Fn(&Entity) -> Vec<ComponentOperation> + Send + Sync + 'static) -> Self {
+        return QueuedMutation { entity, compute: Arc::new(compute) };
+    }
+}
+
+/**
+ * Mutations waiting to be sent, in the order they were pushed.
+ * At most max_per_window are handed out per window, and only one per Entity is in flight at a time,
+ * since every mutation expects the revision the previous one left behind.
+ */
+pub struct BundleQueue {
+    pub max_per_window: usize,
+    pub window: Duration,
+    pending: VecDeque<QueuedMutation>,
+    sent: VecDeque<Instant>,
+    in_flight: HashSet<Pubkey>,
+}
+
+impl BundleQueue {
+    pub fn new(max_per_window: usize, window: Duration) -> Self {
+        return BundleQueue {
+            max_per_window,
+            window,
+            pending: VecDeque::new(),
+            sent: VecDeque::new(),
+            in_flight: HashSet::new(),
+        };
+    }
+
+    pub fn push(&mut self, mutation: QueuedMutation) {
+        self.pending.push_back(mutation);
+    }
+
+    pub fn len(&self) -> usize {
+        return self.pending.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.pending.is_empty();
+    }
+
+    /**
+     * The next mutation that can be sent now, or None if the window is full or every pending Entity is in flight.
+     * Its Entity stays in flight until complete or retry is called.
+     */
+    pub fn pop_ready(&mut self, now: Instant) -> Option<QueuedMutation> {
+        while self.sent.front().is_some_and(|sent| now.duration_since(*sent) >= self.window) {
+            self.sent.pop_front();
+        }
+        if self.sent.len() >= self.max_per_window {
+            return None;
+        }
+
+        let index = self.pending.iter().position(|mutation| !self.in_flight.contains(&mutation.entity))?;
+        let mutation = self.pending.remove(index)?;
+        self.in_flight.insert(mutation.entity);
+        self.sent.push_back(now);
+        return Some(mutation);
+    }
+
+    // When the window has room again, None if it already does
+    pub fn next_slot(&self, now: Instant) -> Option<Instant> {
+        if self.sent.len() < self.max_per_window {
+            return None;
+        }
+        let oldest = self.sent.front()?;
+        return Some(now.max(*oldest + self.window));
+    }
+
+    // The mutation landed or was dropped, later mutations of the Entity can go out
+    pub fn complete(&mut self, entity: &Pubkey) {
+        self.in_flight.remove(entity);
+    }
+
+    // e.g. after StaleEntity or an expired blockhash, the mutation goes back in front of its Entity's other mutations.
+    // Its operations are computed again from the Entity the next prepare fetches
+    pub fn retry(&mut self, mutation: QueuedMutation) {
+        self.in_flight.remove(&mutation.entity);
+        self.pending.push_front(mutation);
+    }
+}
+
+/**
+ * Checks that the Entity after the transaction is exactly what the operations make of the Entity before it:
+ * one revision later, with every component as intended and no others changed.
+ */
+pub fn verify_mutation(address: &Pubkey, before: &Entity, after: &Entity, operations: &Vec<ComponentOperation>) -> Result<()> {
+    if after.revision != before.revision + 1 {
+        return Err(SdkError::UnexpectedRevision(*address, before.revision + 1, after.revision));
+    }
+
+    let intended = apply_component_operations(&before.components, operations)?;
+    let contents = |serialized: &SerializedComponent| (serialized.max_size, serialized.data.clone());
+    for component in intended.keys().chain(after.components.keys()) {
+        if intended.get(component).map(contents) != after.components.get(component).map(contents) {
+            return Err(SdkError::UnexpectedComponent(*address, *component));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{AccountSerialize, AnchorSerialize};
+    use core_ds::constant::ENTITY_LAYOUT_VERSION;
+    use std::collections::HashMap;
+
+    const INSTANCE: u64 = 1;
+
+    fn entity(revision: u64, components: Vec<(Pubkey, Vec<u8>)>) -> Entity {
+        Entity {
+            version: ENTITY_LAYOUT_VERSION,
+            entity_id: 7,
+            instance: INSTANCE,
+            registry: registry::ID,
+            revision,
+            component_revisions: components.iter().map(|(key, _)| (*key, revision)).collect(),
+            components: components.into_iter().map(|(key, data)| (key, SerializedComponent { max_size: 8, data })).collect(),
+        }
+    }
+
+    fn fixture(entity: &Entity) -> HashMap<Pubkey, Vec<u8>> {
+        let mut data = vec![];
+        entity.try_serialize(&mut data).unwrap();
+        HashMap::from([(get_entity_address(INSTANCE, entity.entity_id), data)])
+    }
+
+    fn mutation(entity: Pubkey) -> QueuedMutation {
+        QueuedMutation::new(entity, |_| vec![])
+    }
+
+    #[test]
+    fn queue_limits_each_window() {
+        let mut queue = BundleQueue::new(2, Duration::from_secs(1));
+        for _ in 0..3 {
+            queue.push(mutation(Pubkey::new_unique()));
+        }
+        let start = Instant::now();
+
+        assert!(queue.pop_ready(start).is_some());
+        assert_eq!(queue.next_slot(start), None);
+        assert!(queue.pop_ready(start + Duration::from_millis(500)).is_some());
+        assert!(queue.pop_ready(start + Duration::from_millis(500)).is_none());
+        assert_eq!(queue.next_slot(start + Duration::from_millis(500)), Some(start + Duration::from_secs(1)));
+
+        assert!(queue.pop_ready(start + Duration::from_secs(1)).is_some());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn queue_keeps_one_mutation_per_entity_in_flight() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut queue = BundleQueue::new(10, Duration::from_secs(1));
+        queue.push(mutation(first));
+        queue.push(mutation(first));
+        queue.push(mutation(second));
+        let now = Instant::now();
+
+        assert_eq!(queue.pop_ready(now).unwrap().entity, first);
+        assert_eq!(queue.pop_ready(now).unwrap().entity, second);
+        assert!(queue.pop_ready(now).is_none());
+        assert_eq!(queue.len(), 1);
+
+        queue.complete(&first);
+        assert_eq!(queue.pop_ready(now).unwrap().entity, first);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn retried_mutations_go_first() {
+        let address = Pubkey::new_unique();
+        let mut queue = BundleQueue::new(10, Duration::from_secs(1));
+        queue.push(QueuedMutation::new(address, |_| vec![ComponentOperation::Remove(Pubkey::default())]));
+        queue.push(mutation(address));
+        let now = Instant::now();
+
+        let sent = queue.pop_ready(now).unwrap();
+        assert!(queue.pop_ready(now).is_none());
+        queue.retry(sent);
+        let retried = queue.pop_ready(now).unwrap();
+        assert_eq!((retried.compute)(&entity(0, vec![])).len(), 1);
+    }
+
+    #[test]
+    fn retries_build_on_the_fresh_entity() {
+        let counter = Pubkey::new_unique();
+        let bundle = OffchainBundle::new(Keypair::new());
+        let payer = Keypair::new();
+        let address = get_entity_address(INSTANCE, 7);
+        // Adds one to whatever the counter holds when the mutation is prepared
+        let increment = QueuedMutation::new(address, move |entity| {
+            let value = entity.components[&counter].data[0];
+            vec![ComponentOperation::Modify(counter, vec![value + 1])]
+        });
+
+        let (before, operations, _) = bundle.prepare(&fixture(&entity(0, vec![(counter, vec![1])])), &payer, &increment, Hash::default()).unwrap();
+        assert_eq!(before.revision, 0);
+        assert_eq!(operations.try_to_vec().unwrap(), vec![ComponentOperation::Modify(counter, vec![2])].try_to_vec().unwrap());
+
+        // Someone else changed the counter first, so the retry sends 6 at revision 1 instead of 2 again
+        let (before, operations, tx) = bundle.prepare(&fixture(&entity(1, vec![(counter, vec![5])])), &payer, &increment, Hash::default()).unwrap();
+        assert_eq!(before.revision, 1);
+        let expected = vec![ComponentOperation::Modify(counter, vec![6])];
+        assert_eq!(operations.try_to_vec().unwrap(), expected.try_to_vec().unwrap());
+        assert_eq!(tx.message.instructions[0].data, registry::instruction::ReqMutateComponents {
+            operations: expected,
+            expected_revision: Some(1),
+        }.data());
+    }
+
+    #[test]
+    fn verifies_the_intended_state() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let address = Pubkey::new_unique();
+        let before = entity(3, vec![(a, vec![1]), (b, vec![2])]);
+        let operations = vec![
+            ComponentOperation::Modify(a, vec![9]),
+            ComponentOperation::Remove(b),
+            ComponentOperation::Add(c, SerializedComponent { max_size: 8, data: vec![3] }),
+        ];
+
+        let after = entity(4, vec![(a, vec![9]), (c, vec![3])]);
+        assert!(verify_mutation(&address, &before, &after, &operations).is_ok());
+
+        let skipped = entity(5, vec![(a, vec![9]), (c, vec![3])]);
+        assert!(matches!(verify_mutation(&address, &before, &skipped, &operations), Err(SdkError::UnexpectedRevision(_, 4, 5))));
+
+        let overwritten = entity(4, vec![(a, vec![8]), (c, vec![3])]);
+        assert!(matches!(verify_mutation(&address, &before, &overwritten, &operations), Err(SdkError::UnexpectedComponent(_, component)) if component == a));
+
+        let kept = entity(4, vec![(a, vec![9]), (b, vec![2]), (c, vec![3])]);
+        assert!(matches!(verify_mutation(&address, &before, &kept, &operations), Err(SdkError::UnexpectedComponent(_, component)) if component == b));
+
+        let mut resized = after.clone();
+        resized.components.get_mut(&c).unwrap().max_size = 16;
+        assert!(matches!(verify_mutation(&address, &before, &resized, &operations), Err(SdkError::UnexpectedComponent(_, component)) if component == c));
+    }
+}
@@ -0,0 +1,167 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountDeserialize;
+use core_ds::account::{ComponentData, Entity};
+use core_ds::constant::{SEEDS_COMPONENT_DATA_PREFIX, SEEDS_COMPONENT_UPLOAD_PREFIX};
+use core_ds::state::SerializedComponent;
+
+use crate::error::*;
+use crate::fetcher::*;
+use crate::layout::upgrade_layout;
+
+// Leaves room for the accounts and signatures of a write_component_data transaction
+pub const WRITE_CHUNK_SIZE: usize = 800;
+
+pub fn get_component_data_address(entity: &Pubkey, component: &Pubkey) -> Pubkey {
+    return Pubkey::find_program_address(
+        &[
+            SEEDS_COMPONENT_DATA_PREFIX,
+            entity.as_ref(),
+            component.as_ref()
+        ],
+        &core_ds::ID
+    ).0;
+}
+
+// Staging account used by allocate_component/write_component_chunk/commit_component
+pub fn get_component_upload_address(entity: &Pubkey, component: &Pubkey) -> Pubkey {
+    return Pubkey::find_program_address(
+        &[
+            SEEDS_COMPONENT_UPLOAD_PREFIX,
+            entity.as_ref(),
+            component.as_ref()
+        ],
+        &core_ds::ID
+    ).0;
+}
+
+// Overflow components hold the address of their ComponentData account instead of their data
+pub fn is_overflow(serialized: &SerializedComponent) -> bool {
+    return serialized.is_overflow_reference();
+}
+
+// Header and the written bytes of a ComponentData account
+pub fn read_component_data(address: &Pubkey, data: &[u8]) -> Result<(ComponentData, Vec<u8>)> {
+    let data = upgrade_layout(address, data)?;
+    let account = ComponentData::try_deserialize(&mut &data[..])?;
+    let bytes = data.get(account.header.get_bytes_range()).ok_or(SdkError::CorruptComponentData(*address))?.to_vec();
+    return Ok((account, bytes));
+}
+
+/**
+ * Component bytes as an Action Bundle would deserialize them, fetched from the ComponentData account for overflow components.
+ */
+pub fn get_component_bytes(fetcher: &impl AccountFetcher, entity_address: &Pubkey, entity: &Entity, component: &Pubkey) -> Result<Option<Vec<u8>>> {
+    let serialized = match entity.components.get(component) {
+        Some(serialized) => serialized,
+        None => return Ok(None),
+    };
+    if !is_overflow(serialized) {
+        return Ok(Some(serialized.data.clone()));
+    }
+
+    let address = get_component_data_address(entity_address, component);
+    let data = fetcher.fetch(&address)?.ok_or(SdkError::AccountNotFound(address))?;
+    let (_, bytes) = read_component_data(&address, &data)?;
+    return Ok(Some(bytes));
+}
+
+pub fn fetch_entity(fetcher: &impl AccountFetcher, address: &Pubkey) -> Result<Entity> {
+    return fetch_account::<Entity>(fetcher, address);
+}
+
+// (offset, bytes) pairs to send with write_component_data or write_component_chunk, one per transaction
+pub fn chunk_component_data(data: &[u8], chunk_size: usize) -> Result<Vec<(u64, Vec<u8>)>> {
+    if chunk_size == 0 {
+        return Err(SdkError::InvalidChunkSize);
+    }
+    return Ok(data.chunks(chunk_size)
+        .enumerate()
+        .map(|(i, chunk)| ((i * chunk_size) as u64, chunk.to_vec()))
+        .collect());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AccountSerialize;
+    use core_ds::constant::{COMPONENT_BYTES_OFFSET, ENTITY_LAYOUT_VERSION, OVERFLOW_REFERENCE_MAX_SIZE};
+    use core_ds::state::ComponentBytesHeader;
+    use std::collections::{BTreeMap, HashMap};
+
+    fn entity(components: BTreeMap<Pubkey, SerializedComponent>) -> Entity {
+        return Entity {
+            version: ENTITY_LAYOUT_VERSION,
+            entity_id: 0,
+            instance: 0,
+            registry: Pubkey::new_unique(),
+            revision: 0,
+            component_revisions: components.keys().map(|key| (*key, 0)).collect(),
+            components,
+        };
+    }
+
+    #[test]
+    fn chunks_cover_the_data_in_order() {
+        let data: Vec<u8> = (0..10).collect();
+        let chunks = chunk_component_data(&data, 4).unwrap();
+        assert_eq!(chunks, vec![
+            (0, vec![0, 1, 2, 3]),
+            (4, vec![4, 5, 6, 7]),
+            (8, vec![8, 9]),
+        ]);
+        assert!(chunk_component_data(&[], 4).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_empty_chunks() {
+        assert!(matches!(chunk_component_data(&[1, 2, 3], 0), Err(SdkError::InvalidChunkSize)));
+    }
+
+    #[test]
+    fn reads_inline_components() {
+        let address = Pubkey::new_unique();
+        let component = Pubkey::new_unique();
+        let entity = entity(BTreeMap::from([(component, SerializedComponent { max_size: 8, data: vec![1, 2, 3] })]));
+        let fetcher: HashMap<Pubkey, Vec<u8>> = HashMap::new();
+
+        assert_eq!(get_component_bytes(&fetcher, &address, &entity, &component).unwrap(), Some(vec![1, 2, 3]));
+        assert_eq!(get_component_bytes(&fetcher, &address, &entity, &Pubkey::new_unique()).unwrap(), None);
+    }
+
+    #[test]
+    fn reassembles_overflow_components() {
+        let address = Pubkey::new_unique();
+        let component = Pubkey::new_unique();
+        let data_address = get_component_data_address(&address, &component);
+        let entity = entity(BTreeMap::from([(component, SerializedComponent {
+            max_size: OVERFLOW_REFERENCE_MAX_SIZE,
+            data: data_address.to_bytes().to_vec()
+        })]));
+
+        let bytes: Vec<u8> = (0..=255).cycle().take(2000).collect();
+        let mut header = ComponentBytesHeader::new(address, component, 4096);
+        header.len = bytes.len() as u64;
+        let mut account = vec![];
+        ComponentData { header }.try_serialize(&mut account).unwrap();
+        for (offset, chunk) in chunk_component_data(&bytes, WRITE_CHUNK_SIZE).unwrap() {
+            let start = COMPONENT_BYTES_OFFSET + offset as usize;
+            account.resize(account.len().max(start + chunk.len()), 0);
+            account[start..start + chunk.len()].copy_from_slice(&chunk);
+        }
+        let mut fetcher = HashMap::new();
+
+        assert!(matches!(get_component_bytes(&fetcher, &address, &entity, &component), Err(SdkError::AccountNotFound(missing)) if missing == data_address));
+        fetcher.insert(data_address, account);
+        assert_eq!(get_component_bytes(&fetcher, &address, &entity, &component).unwrap(), Some(bytes));
+    }
+
+    #[test]
+    fn only_the_sentinel_marks_overflow() {
+        let address = Pubkey::new_unique();
+        let component = Pubkey::new_unique();
+        let reference = get_component_data_address(&address, &component).to_bytes().to_vec();
+
+        assert!(!is_overflow(&SerializedComponent { max_size: 32, data: reference.clone() }));
+        assert!(is_overflow(&SerializedComponent { max_size: OVERFLOW_REFERENCE_MAX_SIZE, data: reference }));
+    }
+}
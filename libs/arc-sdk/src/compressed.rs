@@ -0,0 +1,159 @@
+use anchor_lang::prelude::{AccountMeta, Pubkey};
+use anchor_lang::AnchorDeserialize;
+use anchor_lang::solana_program::keccak::hashv;
+use core_ds::event::CompressedEntityEvent;
+use core_ds::state::CompressedEntity;
+use spl_account_compression::events::{AccountCompressionEvent, ApplicationDataEvent};
+use spl_concurrent_merkle_tree::node::{empty_node, Node, EMPTY};
+use std::collections::BTreeMap;
+
+use crate::error::*;
+
+/**
+ * Decodes the instruction data of a Noop inner instruction made by CoreDS.
+ * Tree changelogs (and anything that isn't a compressed Entity event) come back as None.
+ */
+pub fn decode_compressed_event(noop_data: &[u8]) -> Result<Option<CompressedEntityEvent>> {
+    let event = AccountCompressionEvent::try_from_slice(noop_data)
+        .map_err(|err| SdkError::Decode(err.into()))?;
+    match event {
+        AccountCompressionEvent::ApplicationData(ApplicationDataEvent::V1(data)) => {
+            Ok(CompressedEntityEvent::try_from_slice(&data.application_data).ok())
+        },
+        AccountCompressionEvent::ChangeLog(_) => Ok(None),
+    }
+}
+
+/**
+ * Rebuilds compressed Entities (and the leaves needed for proofs) from CoreDS events, fed in the order they happened.
+ */
+#[derive(Default)]
+pub struct CompressedIndex {
+    pub entities: BTreeMap<(Pubkey, u32), CompressedEntity>,
+    pub leaves: BTreeMap<Pubkey, Vec<Node>>,
+}
+
+impl CompressedIndex {
+    pub fn apply(&mut self, event: CompressedEntityEvent) -> Result<()> {
+        match event {
+            CompressedEntityEvent::Created { merkle_tree, index, entity } |
+            CompressedEntityEvent::Modified { merkle_tree, index, entity } => {
+                self.set_leaf(merkle_tree, index, entity.hash()?);
+                self.entities.insert((merkle_tree, index), entity);
+            },
+            CompressedEntityEvent::Removed { merkle_tree, index } => {
+                self.set_leaf(merkle_tree, index, EMPTY);
+                self.entities.remove(&(merkle_tree, index));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, merkle_tree: &Pubkey, index: u32) -> Option<&CompressedEntity> {
+        return self.entities.get(&(*merkle_tree, index));
+    }
+
+    // Root and proof for a leaf, as modify/remove_compressed_entity expect them
+    pub fn get_proof(&self, merkle_tree: &Pubkey, index: u32, max_depth: u32) -> (Node, Vec<Node>) {
+        let leaves = self.leaves.get(merkle_tree).cloned().unwrap_or_default();
+        return get_proof(&leaves, index, max_depth);
+    }
+
+    fn set_leaf(&mut self, merkle_tree: Pubkey, index: u32, leaf: Node) {
+        let leaves = self.leaves.entry(merkle_tree).or_default();
+        if leaves.len() <= index as usize {
+            leaves.resize(index as usize + 1, EMPTY);
+        }
+        leaves[index as usize] = leaf;
+    }
+}
+
+pub fn get_proof(leaves: &[Node], index: u32, max_depth: u32) -> (Node, Vec<Node>) {
+    let mut level = leaves.to_vec();
+    let mut position = index as usize;
+    let mut proof = vec![];
+    for depth in 0..max_depth {
+        let empty = empty_node(depth);
+        proof.push(*level.get(position ^ 1).unwrap_or(&empty));
+        level = level.chunks(2)
+            .map(|pair| hashv(&[&pair[0], pair.get(1).unwrap_or(&empty)]).to_bytes())
+            .collect();
+        position >>= 1;
+    }
+    let root = *level.first().unwrap_or(&empty_node(max_depth));
+    return (root, proof);
+}
+
+// Proof nodes go in as remaining accounts
+pub fn get_proof_accounts(proof: &[Node]) -> Vec<AccountMeta> {
+    return proof.iter()
+        .map(|node| AccountMeta::new_readonly(Pubkey::new_from_array(*node), false))
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core_ds::state::SerializedComponent;
+    use spl_concurrent_merkle_tree::concurrent_merkle_tree::ConcurrentMerkleTree;
+
+    const MAX_DEPTH: usize = 3;
+
+    fn entity(entity_id: u64, data: Vec<u8>) -> CompressedEntity {
+        return CompressedEntity {
+            entity_id,
+            instance: 0,
+            registry: Pubkey::default(),
+            components: BTreeMap::from([(Pubkey::default(), SerializedComponent { max_size: 8, data })]),
+        };
+    }
+
+    #[test]
+    fn empty_tree_proof() {
+        let (root, proof) = get_proof(&[], 0, MAX_DEPTH as u32);
+        assert_eq!(root, empty_node(MAX_DEPTH as u32));
+        assert_eq!(proof, (0..MAX_DEPTH as u32).map(empty_node).collect::<Vec<_>>());
+    }
+
+    // Replays create, modify and remove the way CoreDS drives SPL Account Compression, with proofs from the index
+    #[test]
+    fn proofs_match_the_tree() {
+        let mut tree = ConcurrentMerkleTree::<MAX_DEPTH, 8>::new();
+        tree.initialize().unwrap();
+        let mut index = CompressedIndex::default();
+        let merkle_tree = Pubkey::new_unique();
+
+        for entity_id in 0..3 {
+            let entity = entity(entity_id, vec![entity_id as u8]);
+            tree.append(entity.hash().unwrap()).unwrap();
+            index.apply(CompressedEntityEvent::Created { merkle_tree, index: entity_id as u32, entity }).unwrap();
+            assert_eq!(index.get_proof(&merkle_tree, 0, MAX_DEPTH as u32).0, tree.get_root());
+        }
+
+        let previous = index.get(&merkle_tree, 1).unwrap().hash().unwrap();
+        let modified = entity(1, vec![7, 7]);
+        let (root, proof) = index.get_proof(&merkle_tree, 1, MAX_DEPTH as u32);
+        tree.set_leaf(root, previous, modified.hash().unwrap(), &proof, 1).unwrap();
+        index.apply(CompressedEntityEvent::Modified { merkle_tree, index: 1, entity: modified.clone() }).unwrap();
+        assert_eq!(index.get_proof(&merkle_tree, 1, MAX_DEPTH as u32).0, tree.get_root());
+        assert_eq!(index.get(&merkle_tree, 1).unwrap().hash().unwrap(), modified.hash().unwrap());
+
+        let previous = index.get(&merkle_tree, 2).unwrap().hash().unwrap();
+        let (root, proof) = index.get_proof(&merkle_tree, 2, MAX_DEPTH as u32);
+        tree.set_leaf(root, previous, EMPTY, &proof, 2).unwrap();
+        index.apply(CompressedEntityEvent::Removed { merkle_tree, index: 2 }).unwrap();
+        assert_eq!(index.get_proof(&merkle_tree, 0, MAX_DEPTH as u32).0, tree.get_root());
+        assert!(index.get(&merkle_tree, 2).is_none());
+    }
+
+    #[test]
+    fn trees_are_kept_apart() {
+        let mut index = CompressedIndex::default();
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        index.apply(CompressedEntityEvent::Created { merkle_tree: first, index: 0, entity: entity(0, vec![1]) }).unwrap();
+
+        assert!(index.get(&second, 0).is_none());
+        assert_eq!(index.get_proof(&second, 0, MAX_DEPTH as u32).0, empty_node(MAX_DEPTH as u32));
+        assert_ne!(index.get_proof(&first, 0, MAX_DEPTH as u32).0, empty_node(MAX_DEPTH as u32));
+    }
+}
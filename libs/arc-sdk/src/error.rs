@@ -0,0 +1,49 @@
+use anchor_lang::prelude::Pubkey;
+use std::fmt;
+
+#[derive(Debug)]
+pub enum SdkError {
+    AccountNotFound(Pubkey),
+    Fetch(String),
+    Decode(anchor_lang::error::Error),
+    CorruptComponentData(Pubkey),
+    UnsupportedVersion(Pubkey, u8),
+    InvalidInstruction(String),
+    UnorderedHistory(u64, u64),
+    HistoryGap(Pubkey, u64, u64),
+    HistoryMismatch(Pubkey, Pubkey),
+    MissingComponentData(Pubkey, Pubkey),
+    UnexpectedRevision(Pubkey, u64, u64),
+    UnexpectedComponent(Pubkey, Pubkey),
+    InvalidChunkSize,
+}
+
+impl fmt::Display for SdkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SdkError::AccountNotFound(address) => write!(f, "Account {} not found", address),
+            SdkError::Fetch(msg) => write!(f, "Failed to fetch account: {}", msg),
+            SdkError::Decode(err) => write!(f, "Failed to decode account: {}", err),
+            SdkError::CorruptComponentData(address) => write!(f, "Component data in {} is shorter than its length", address),
+            SdkError::UnsupportedVersion(address, version) => write!(f, "Account {} has layout version {}, newer than this SDK supports", address, version),
+            SdkError::InvalidInstruction(msg) => write!(f, "Failed to decode instruction: {}", msg),
+            SdkError::UnorderedHistory(slot, previous) => write!(f, "History isn't in order, slot {} comes after slot {}", slot, previous),
+            SdkError::HistoryGap(address, expected, found) => write!(f, "History of {} is missing revisions, expected {} but found {}", address, expected, found),
+            SdkError::HistoryMismatch(address, component) => write!(f, "Component {} of {} doesn't match its history", component, address),
+            SdkError::MissingComponentData(address, component) => write!(f, "No data in the history for component {} of {}", component, address),
+            SdkError::UnexpectedRevision(address, expected, found) => write!(f, "Entity {} is at revision {}, expected {}", address, found, expected),
+            SdkError::UnexpectedComponent(address, component) => write!(f, "Component {} of {} doesn't match the intended state", component, address),
+            SdkError::InvalidChunkSize => write!(f, "Chunk size must be greater than 0"),
+        }
+    }
+}
+
+impl std::error::Error for SdkError {}
+
+impl From<anchor_lang::error::Error> for SdkError {
+    fn from(err: anchor_lang::error::Error) -> Self {
+        SdkError::Decode(err)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, SdkError>;
@@ -0,0 +1,25 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::AccountDeserialize;
+use std::collections::HashMap;
+
+use crate::error::*;
+use crate::layout::decode_account;
+
+/**
+ * Where account data comes from, so the SDK works the same over an RPC client, a cache or test fixtures.
+ */
+pub trait AccountFetcher {
+    // None when the account doesn't exist
+    fn fetch(&self, address: &Pubkey) -> Result<Option<Vec<u8>>>;
+}
+
+impl AccountFetcher for HashMap<Pubkey, Vec<u8>> {
+    fn fetch(&self, address: &Pubkey) -> Result<Option<Vec<u8>>> {
+        Ok(self.get(address).cloned())
+    }
+}
+
+pub fn fetch_account<T: AccountDeserialize>(fetcher: &impl AccountFetcher, address: &Pubkey) -> Result<T> {
+    let data = fetcher.fetch(address)?.ok_or(SdkError::AccountNotFound(*address))?;
+    decode_account(address, &data)
+}
@@ -0,0 +1,553 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::keccak;
+use anchor_lang::{AnchorDeserialize, Discriminator, Id};
+use core_ds::account::Entity;
+use core_ds::event::{ComponentDataWritten, EntityMutation};
+use core_ds::instruction;
+use core_ds::state::{ComponentHash, ComponentOperation, SerializedComponent};
+use spl_account_compression::events::{AccountCompressionEvent, ApplicationDataEvent};
+use spl_account_compression::Noop;
+use std::collections::HashMap;
+
+use crate::component::get_component_data_address;
+use crate::error::*;
+
+/**
+ * An instruction a transaction ran, top level or inner, as an RPC node returns it.
+ */
+pub struct HistoryInstruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub data: Vec<u8>,
+    pub stack_height: u32, // 1 for top level instructions, one more for every CPI below that
+}
+
+/**
+ * A successful transaction from an Entity's history: the slot it landed in and every instruction it ran in execution order.
+ */
+pub struct HistoryTransaction {
+    pub slot: u64,
+    pub instructions: Vec<HistoryInstruction>,
+}
+
+impl HistoryTransaction {
+    // Program that invoked the instruction at index, None for top level instructions
+    pub fn get_caller(&self, index: usize) -> Option<&Pubkey> {
+        let stack_height = self.instructions[index].stack_height;
+        let caller = self.instructions[..index].iter().rev().find(|ix| ix.stack_height < stack_height)?;
+        if caller.stack_height + 1 != stack_height {
+            return None;
+        }
+        return Some(&caller.program_id);
+    }
+}
+
+// Application data of the Noop instructions CoreDS invoked itself, in the order they ran
+fn decode_core_ds_data(transaction: &HistoryTransaction) -> Result<Vec<Vec<u8>>> {
+    let mut logged = vec![];
+    for (i, ix) in transaction.instructions.iter().enumerate() {
+        if ix.program_id != Noop::id() || transaction.get_caller(i) != Some(&core_ds::ID) {
+            continue;
+        }
+        let event = AccountCompressionEvent::try_from_slice(&ix.data).map_err(|err| SdkError::Decode(err.into()))?;
+        // Compressed Entity events and tree changelogs are also logged through the Noop program
+        match event {
+            AccountCompressionEvent::ApplicationData(ApplicationDataEvent::V1(data)) => logged.push(data.application_data),
+            AccountCompressionEvent::ChangeLog(_) => continue,
+        };
+    }
+    Ok(logged)
+}
+
+// None when the data is another kind of event
+fn decode_event<T: AnchorDeserialize + Discriminator>(data: &[u8]) -> Result<Option<T>> {
+    if data.len() < 8 || data[..8] != T::discriminator() {
+        return Ok(None);
+    }
+    Ok(Some(T::try_from_slice(&data[8..]).map_err(|err| SdkError::Decode(err.into()))?))
+}
+
+/**
+ * EntityMutation records CoreDS logged in a transaction. Only Noop instructions CoreDS invoked itself are read,
+ * so records other programs log through the Noop program are ignored.
+ */
+pub fn decode_mutation_records(transaction: &HistoryTransaction) -> Result<Vec<EntityMutation>> {
+    let mut records = vec![];
+    for data in decode_core_ds_data(transaction)? {
+        if let Some(record) = decode_event::<EntityMutation>(&data)? {
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+// Position of the Entity in the upload instructions' accounts, in the order of their CoreDS accounts structs
+const ALLOCATE_ENTITY_INDEX: usize = 2;
+const WRITE_CHUNK_ENTITY_INDEX: usize = 0;
+const COMMIT_ENTITY_INDEX: usize = 2;
+const CANCEL_ENTITY_INDEX: usize = 2;
+
+fn get_account(ix: &HistoryInstruction, index: usize) -> Result<Pubkey> {
+    return ix.accounts.get(index).copied()
+        .ok_or_else(|| SdkError::InvalidInstruction(format!("missing account {} of a CoreDS instruction", index)));
+}
+
+/**
+ * Component bytes by keccak hash. Mutation records only carry hashes, the bytes come
+ * from the CoreDS instructions that wrote them or from known Entity states.
+ */
+#[derive(Default)]
+pub struct ComponentStore {
+    pub data: HashMap<[u8; 32], Vec<u8>>,
+    // Staged uploads by (entity, component), until they're committed or cancelled
+    uploads: HashMap<(Pubkey, Pubkey), Vec<u8>>,
+}
+
+impl ComponentStore {
+    pub fn insert(&mut self, data: Vec<u8>) {
+        self.data.insert(keccak::hash(&data).to_bytes(), data);
+    }
+
+    pub fn get(&self, hash: &[u8; 32]) -> Option<&Vec<u8>> {
+        return self.data.get(hash);
+    }
+
+    // e.g. the Entity as it is now, or the source of a clone whose history isn't available
+    pub fn insert_entity(&mut self, entity: &Entity) {
+        for component in entity.components.values() {
+            self.insert(component.data.clone());
+        }
+    }
+
+    // Uploads span transactions, so transactions have to be inserted in the order they ran
+    pub fn insert_transaction(&mut self, transaction: &HistoryTransaction) -> Result<()> {
+        for ix in transaction.instructions.iter() {
+            if ix.program_id == core_ds::ID {
+                self.insert_instruction(ix)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Picks the component bytes out of a CoreDS instruction, anything else is ignored
+    pub fn insert_instruction(&mut self, ix: &HistoryInstruction) -> Result<()> {
+        if ix.data.len() < 8 {
+            return Ok(());
+        }
+        let (discriminator, mut bytes) = ix.data.split_at(8);
+        let decode_error = |err: std::io::Error| SdkError::Decode(err.into());
+
+        if discriminator == instruction::InitEntity::discriminator() {
+            let args = instruction::InitEntity::deserialize(&mut bytes).map_err(decode_error)?;
+            self.insert_components(args.components.into_values());
+        } else if discriminator == instruction::AddComponents::discriminator() {
+            let args = instruction::AddComponents::deserialize(&mut bytes).map_err(decode_error)?;
+            self.insert_components(args.components.into_iter().map(|tuple| tuple.1));
+        } else if discriminator == instruction::ModifyComponents::discriminator() {
+            let args = instruction::ModifyComponents::deserialize(&mut bytes).map_err(decode_error)?;
+            args.components.into_iter().for_each(|tuple| self.insert(tuple.1));
+        } else if discriminator == instruction::MutateComponents::discriminator() {
+            let args = instruction::MutateComponents::deserialize(&mut bytes).map_err(decode_error)?;
+            for op in args.operations {
+                match op {
+                    ComponentOperation::Add(_, serialized) => self.insert(serialized.data),
+                    ComponentOperation::Modify(_, data) => self.insert(data),
+                    ComponentOperation::Remove(_) => {},
+                }
+            }
+        } else if discriminator == instruction::BatchAddComponents::discriminator() {
+            let args = instruction::BatchAddComponents::deserialize(&mut bytes).map_err(decode_error)?;
+            self.insert_components(args.changes.into_iter().flatten().map(|tuple| tuple.1));
+        } else if discriminator == instruction::BatchModifyComponents::discriminator() {
+            let args = instruction::BatchModifyComponents::deserialize(&mut bytes).map_err(decode_error)?;
+            args.changes.into_iter().flatten().for_each(|tuple| self.insert(tuple.1));
+        } else if discriminator == instruction::AllocateComponent::discriminator() {
+            let args = instruction::AllocateComponent::deserialize(&mut bytes).map_err(decode_error)?;
+            self.uploads.insert((get_account(ix, ALLOCATE_ENTITY_INDEX)?, args.component), vec![]);
+        } else if discriminator == instruction::WriteComponentChunk::discriminator() {
+            let args = instruction::WriteComponentChunk::deserialize(&mut bytes).map_err(decode_error)?;
+            let upload = self.uploads.entry((get_account(ix, WRITE_CHUNK_ENTITY_INDEX)?, args._component)).or_default();
+            let end = args.offset as usize + args.data.len();
+            if upload.len() < end {
+                upload.resize(end, 0);
+            }
+            upload[args.offset as usize..end].copy_from_slice(&args.data);
+        } else if discriminator == instruction::CommitComponent::discriminator() {
+            let args = instruction::CommitComponent::deserialize(&mut bytes).map_err(decode_error)?;
+            if let Some(upload) = self.uploads.remove(&(get_account(ix, COMMIT_ENTITY_INDEX)?, args.component)) {
+                self.insert(upload);
+            }
+        } else if discriminator == instruction::CancelComponent::discriminator() {
+            let args = instruction::CancelComponent::deserialize(&mut bytes).map_err(decode_error)?;
+            self.uploads.remove(&(get_account(ix, CANCEL_ENTITY_INDEX)?, args._component));
+        }
+        Ok(())
+    }
+
+    fn insert_components(&mut self, components: impl Iterator<Item = SerializedComponent>) {
+        for component in components {
+            self.insert(component.data);
+        }
+    }
+
+    // Overflow components hold their ComponentData address, which never shows up in instruction data
+    fn get_component(&self, entity: &Pubkey, component: &Pubkey, hash: &ComponentHash) -> Option<Vec<u8>> {
+        if let Some(data) = self.get(&hash.hash) {
+            return Some(data.clone());
+        }
+        let reference = get_component_data_address(entity, component).to_bytes().to_vec();
+        if keccak::hash(&reference).to_bytes() == hash.hash {
+            return Some(reference);
+        }
+        return None;
+    }
+}
+
+/**
+ * Applies one record to the Entity, checking that every changed component matches the record's old hash first.
+ * Creation records are revision 0 and only apply to an empty Entity, every other record has to be the next revision.
+ */
+pub fn apply_mutation(address: &Pubkey, entity: &mut Entity, record: &EntityMutation, store: &ComponentStore) -> Result<()> {
+    let creation = record.revision == 0 && entity.revision == 0 && entity.components.is_empty();
+    if !creation && record.revision != entity.revision + 1 {
+        return Err(SdkError::HistoryGap(*address, entity.revision + 1, record.revision));
+    }
+
+    for change in record.changes.iter() {
+        let current = entity.components.get(&change.component).map(ComponentHash::new);
+        if current != change.old {
+            return Err(SdkError::HistoryMismatch(*address, change.component));
+        }
+        match change.new.as_ref() {
+            Some(new) => {
+                let data = store.get_component(address, &change.component, new)
+                    .ok_or(SdkError::MissingComponentData(*address, change.component))?;
+                entity.components.insert(change.component, SerializedComponent {
+                    max_size: new.max_size,
+                    data
+                });
+                entity.component_revisions.insert(change.component, record.revision);
+            },
+            None => {
+                entity.components.remove(&change.component);
+                entity.component_revisions.remove(&change.component);
+            }
+        }
+    }
+    entity.revision = record.revision;
+    Ok(())
+}
+
+/**
+ * The Entity as it was at `revision`, rebuilt from its transaction history, oldest transaction first.
+ * base is where the replay starts: the Entity before creation (revision 0, no components) or any known snapshot of it.
+ * Records carry their slot, so the revision for a point in time can be picked from decode_mutation_records.
+ * Clones need the bytes of their source, so include the source's history too (only this Entity's records are applied).
+ */
+pub fn replay_entity(address: &Pubkey, base: Entity, history: &[HistoryTransaction], revision: u64) -> Result<Entity> {
+    let mut store = ComponentStore::default();
+    store.insert_entity(&base);
+    let mut records = vec![];
+    let mut slot = 0;
+    for transaction in history {
+        // Staged uploads only come together in the order they were written
+        if transaction.slot < slot {
+            return Err(SdkError::UnorderedHistory(transaction.slot, slot));
+        }
+        slot = transaction.slot;
+        store.insert_transaction(transaction)?;
+        records.extend(decode_mutation_records(transaction)?.into_iter().filter(|record| record.entity == *address));
+    }
+
+    let mut entity = base;
+    for record in records.iter() {
+        if record.revision > revision {
+            break;
+        }
+        // Already part of the base snapshot
+        let creation = record.revision == 0 && entity.components.is_empty();
+        if record.revision <= entity.revision && !creation {
+            continue;
+        }
+        apply_mutation(address, &mut entity, record, &store)?;
+    }
+
+    if entity.revision != revision {
+        return Err(SdkError::HistoryGap(*address, entity.revision + 1, revision));
+    }
+    return Ok(entity);
+}
+
+/**
+ * Bytes of an overflow component's ComponentData account as they were at `revision`, None if the Entity didn't hold
+ * the component as an overflow component then. Writes only log the bytes they changed, so the history has to go back
+ * to when the component was added. Oldest transaction first, like replay_entity.
+ */
+pub fn replay_component_data(address: &Pubkey, component: &Pubkey, history: &[HistoryTransaction], revision: u64) -> Result<Option<Vec<u8>>> {
+    let reference = keccak::hash(&get_component_data_address(address, component).to_bytes()).to_bytes();
+    let is_reference = |hash: &Option<ComponentHash>| hash.as_ref().is_some_and(|hash| hash.hash == reference);
+    let mut bytes: Option<Vec<u8>> = None;
+    let mut slot = 0;
+    for transaction in history {
+        if transaction.slot < slot {
+            return Err(SdkError::UnorderedHistory(transaction.slot, slot));
+        }
+        slot = transaction.slot;
+
+        // Each write is logged right after its record, so both are read in the order they ran
+        for data in decode_core_ds_data(transaction)? {
+            if let Some(record) = decode_event::<EntityMutation>(&data)? {
+                if record.entity != *address || record.revision > revision {
+                    continue;
+                }
+                for change in record.changes.iter().filter(|change| change.component == *component) {
+                    if !is_reference(&change.new) {
+                        bytes = None;
+                    } else if !is_reference(&change.old) {
+                        // add_overflow_component starts from an empty ComponentData account
+                        bytes = Some(vec![]);
+                    }
+                }
+            } else if let Some(write) = decode_event::<ComponentDataWritten>(&data)? {
+                if write.entity != *address || write.component != *component || write.revision > revision {
+                    continue;
+                }
+                let current = bytes.as_mut().ok_or(SdkError::MissingComponentData(*address, *component))?;
+                let end = write.offset as usize + write.data.len();
+                if current.len() < end {
+                    current.resize(end, 0);
+                }
+                current[write.offset as usize..end].copy_from_slice(&write.data);
+            }
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{AnchorSerialize, Event, InstructionData, ToAccountMetas};
+    use core_ds::constant::{ENTITY_LAYOUT_VERSION, OVERFLOW_REFERENCE_MAX_SIZE};
+    use core_ds::event::CompressedEntityEvent;
+    use core_ds::state::ComponentChange;
+    use spl_account_compression::events::ApplicationDataEventV1;
+    use std::collections::BTreeMap;
+
+    fn noop_data(application_data: Vec<u8>) -> Vec<u8> {
+        let event = AccountCompressionEvent::ApplicationData(ApplicationDataEvent::V1(ApplicationDataEventV1 { application_data }));
+        event.try_to_vec().unwrap()
+    }
+
+    fn ix(program_id: Pubkey, accounts: Vec<Pubkey>, data: Vec<u8>, stack_height: u32) -> HistoryInstruction {
+        HistoryInstruction { program_id, accounts, data, stack_height }
+    }
+
+    fn record(entity: Pubkey, revision: u64, component: Pubkey, old: Option<(u64, &[u8])>, new: Option<(u64, &[u8])>) -> EntityMutation {
+        let hash = |(max_size, data): (u64, &[u8])| ComponentHash { max_size, hash: keccak::hash(data).to_bytes() };
+        EntityMutation {
+            entity,
+            revision,
+            slot: 0,
+            action_bundle: Pubkey::default(),
+            changes: vec![ComponentChange { component, old: old.map(hash), new: new.map(hash) }],
+        }
+    }
+
+    // A Registry request CPIing into CoreDS, which logs the record through the Noop program
+    fn request(slot: u64, accounts: Vec<Pubkey>, data: Vec<u8>, record: Option<&EntityMutation>) -> HistoryTransaction {
+        let mut instructions = vec![
+            ix(registry::ID, vec![], vec![], 1),
+            ix(core_ds::ID, accounts, data, 2),
+        ];
+        if let Some(record) = record {
+            instructions.push(ix(Noop::id(), vec![], noop_data(record.data()), 3));
+        }
+        HistoryTransaction { slot, instructions }
+    }
+
+    fn empty_entity() -> Entity {
+        Entity {
+            version: ENTITY_LAYOUT_VERSION,
+            entity_id: 0,
+            instance: 0,
+            registry: registry::ID,
+            revision: 0,
+            components: BTreeMap::new(),
+            component_revisions: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn callers_follow_the_stack() {
+        let programs: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let transaction = HistoryTransaction {
+            slot: 0,
+            instructions: vec![
+                ix(programs[0], vec![], vec![], 1),
+                ix(programs[1], vec![], vec![], 2),
+                ix(programs[2], vec![], vec![], 3),
+                ix(programs[3], vec![], vec![], 2),
+                ix(programs[4], vec![], vec![], 1),
+                // Heights can't skip a level, there's no caller to attribute this to
+                ix(programs[2], vec![], vec![], 3),
+            ],
+        };
+        let callers: Vec<Option<&Pubkey>> = (0..6).map(|i| transaction.get_caller(i)).collect();
+        assert_eq!(callers, vec![None, Some(&programs[0]), Some(&programs[1]), Some(&programs[0]), None, None]);
+    }
+
+    #[test]
+    fn only_decodes_records_core_ds_logged() {
+        let entity = Pubkey::new_unique();
+        let component = Pubkey::new_unique();
+        let genuine = record(entity, 1, component, None, Some((8, &[1])));
+        let forged = noop_data(record(entity, 2, component, None, Some((8, &[2]))).data());
+        let compressed = CompressedEntityEvent::Removed { merkle_tree: Pubkey::new_unique(), index: 0 };
+
+        let transaction = HistoryTransaction {
+            slot: 0,
+            instructions: vec![
+                // Straight from the transaction or from another program, not CoreDS
+                ix(Noop::id(), vec![], forged.clone(), 1),
+                ix(registry::ID, vec![], vec![], 1),
+                ix(Noop::id(), vec![], forged.clone(), 2),
+                ix(Pubkey::new_unique(), vec![], vec![], 2),
+                ix(Noop::id(), vec![], forged, 3),
+                // CoreDS also logs compressed Entity events
+                ix(core_ds::ID, vec![], vec![], 2),
+                ix(Noop::id(), vec![], noop_data(compressed.try_to_vec().unwrap()), 3),
+                ix(Noop::id(), vec![], noop_data(genuine.data()), 3),
+            ],
+        };
+
+        let records = decode_mutation_records(&transaction).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!((records[0].entity, records[0].revision), (entity, 1));
+    }
+
+    #[test]
+    fn upload_entity_positions_match_core_ds() {
+        let entity = Pubkey::new_unique();
+        let other = Pubkey::new_unique;
+        let allocate = core_ds::accounts::AllocateComponent { payer: other(), system_program: other(), entity, component_upload: other(), registry_signer: other() };
+        let write = core_ds::accounts::WriteComponentChunk { entity, component_upload: other(), registry_signer: other() };
+        let commit = core_ds::accounts::CommitComponent { payer: other(), system_program: other(), entity, component_upload: other(), action_bundle: other(), registry_signer: other(), noop_program: other() };
+        let cancel = core_ds::accounts::CancelComponent { benefactor: other(), system_program: other(), entity, component_upload: other(), registry_signer: other() };
+
+        assert_eq!(allocate.to_account_metas(None)[ALLOCATE_ENTITY_INDEX].pubkey, entity);
+        assert_eq!(write.to_account_metas(None)[WRITE_CHUNK_ENTITY_INDEX].pubkey, entity);
+        assert_eq!(commit.to_account_metas(None)[COMMIT_ENTITY_INDEX].pubkey, entity);
+        assert_eq!(cancel.to_account_metas(None)[CANCEL_ENTITY_INDEX].pubkey, entity);
+    }
+
+    fn create(slot: u64, entity: Pubkey, component: Pubkey, data: u8) -> HistoryTransaction {
+        let components = BTreeMap::from([(component, SerializedComponent { max_size: 8, data: vec![data] })]);
+        return request(slot, vec![], instruction::InitEntity { entity_id: 0, components }.data(),
+            Some(&record(entity, 0, component, None, Some((8, &[data])))));
+    }
+
+    fn modify(slot: u64, entity: Pubkey, revision: u64, component: Pubkey, old: u8, new: u8) -> HistoryTransaction {
+        return request(slot, vec![], instruction::ModifyComponents { components: vec![(component, vec![new])] }.data(),
+            Some(&record(entity, revision, component, Some((8, &[old])), Some((8, &[new])))));
+    }
+
+    #[test]
+    fn replays_every_revision() {
+        let entity = Pubkey::new_unique();
+        let neighbour = Pubkey::new_unique();
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let upload_accounts = |entity: Pubkey| vec![Pubkey::default(), Pubkey::default(), entity];
+
+        let history = vec![
+            create(10, entity, a, 1),
+            modify(11, entity, 1, a, 1, 2),
+            // Another Entity staging the same component at the same time
+            request(12, upload_accounts(entity), instruction::AllocateComponent { component: b, max_size: 16 }.data(), None),
+            request(12, upload_accounts(neighbour), instruction::AllocateComponent { component: b, max_size: 16 }.data(), None),
+            request(12, vec![neighbour], instruction::WriteComponentChunk { _component: b, offset: 0, data: vec![9, 9, 9] }.data(), None),
+            request(13, vec![entity], instruction::WriteComponentChunk { _component: b, offset: 1, data: vec![4] }.data(), None),
+            request(13, vec![entity], instruction::WriteComponentChunk { _component: b, offset: 0, data: vec![3] }.data(), None),
+            request(14, upload_accounts(entity), instruction::CommitComponent { component: b }.data(),
+                Some(&record(entity, 2, b, None, Some((16, &[3, 4]))))),
+        ];
+
+        let first = replay_entity(&entity, empty_entity(), &history, 0).unwrap();
+        assert_eq!(first.components[&a].data, vec![1]);
+        let second = replay_entity(&entity, empty_entity(), &history, 1).unwrap();
+        assert_eq!(second.components[&a].data, vec![2]);
+        assert_eq!(second.component_revisions[&a], 1);
+
+        let last = replay_entity(&entity, empty_entity(), &history, 2).unwrap();
+        assert_eq!(last.revision, 2);
+        assert_eq!(last.components[&a].data, vec![2]);
+        assert_eq!((last.components[&b].max_size, last.components[&b].data.clone()), (16, vec![3, 4]));
+        assert_eq!(last.component_revisions[&b], 2);
+
+        // Continuing from a snapshot only needs the history since
+        let resumed = replay_entity(&entity, second, &history[2..], 2).unwrap();
+        assert_eq!(resumed.components[&b].data, vec![3, 4]);
+    }
+
+    #[test]
+    fn rejects_incomplete_or_unordered_history() {
+        let entity = Pubkey::new_unique();
+        let a = Pubkey::new_unique();
+
+        let unordered = [modify(11, entity, 1, a, 1, 2), create(10, entity, a, 1)];
+        assert!(matches!(replay_entity(&entity, empty_entity(), &unordered, 1), Err(SdkError::UnorderedHistory(10, 11))));
+
+        let missing = [create(10, entity, a, 1), modify(12, entity, 2, a, 2, 3)];
+        assert!(matches!(replay_entity(&entity, empty_entity(), &missing, 2), Err(SdkError::HistoryGap(_, 1, 2))));
+
+        let complete = [create(10, entity, a, 1), modify(11, entity, 1, a, 1, 2)];
+        assert!(matches!(replay_entity(&entity, empty_entity(), &complete, 2), Err(SdkError::HistoryGap(_, 2, 2))));
+    }
+
+    // A Registry request whose CoreDS call logs each of the events, in order
+    fn logged(slot: u64, events: Vec<Vec<u8>>) -> HistoryTransaction {
+        let mut instructions = vec![
+            ix(registry::ID, vec![], vec![], 1),
+            ix(core_ds::ID, vec![], vec![], 2),
+        ];
+        instructions.extend(events.into_iter().map(|event| ix(Noop::id(), vec![], noop_data(event), 3)));
+        HistoryTransaction { slot, instructions }
+    }
+
+    fn write(entity: Pubkey, revision: u64, component: Pubkey, offset: u64, data: Vec<u8>) -> Vec<u8> {
+        return ComponentDataWritten { entity, component, revision, offset, data }.data();
+    }
+
+    #[test]
+    fn replays_overflow_writes() {
+        let entity = Pubkey::new_unique();
+        let component = Pubkey::new_unique();
+        let reference = get_component_data_address(&entity, &component).to_bytes();
+        let reference = Some((OVERFLOW_REFERENCE_MAX_SIZE, &reference[..]));
+
+        let history = vec![
+            create(10, entity, Pubkey::new_unique(), 1),
+            logged(11, vec![record(entity, 1, component, None, reference).data()]),
+            logged(12, vec![
+                record(entity, 2, component, reference, reference).data(),
+                write(entity, 2, component, 0, vec![1, 2, 3]),
+                // Another component and Entity writing at the same time
+                write(entity, 2, Pubkey::new_unique(), 0, vec![7]),
+                write(Pubkey::new_unique(), 2, component, 0, vec![7]),
+            ]),
+            logged(13, vec![record(entity, 3, component, reference, reference).data(), write(entity, 3, component, 2, vec![4, 5])]),
+            logged(14, vec![record(entity, 4, component, reference, None).data()]),
+        ];
+
+        assert_eq!(replay_component_data(&entity, &component, &history, 0).unwrap(), None);
+        assert_eq!(replay_component_data(&entity, &component, &history, 1).unwrap(), Some(vec![]));
+        assert_eq!(replay_component_data(&entity, &component, &history, 2).unwrap(), Some(vec![1, 2, 3]));
+        assert_eq!(replay_component_data(&entity, &component, &history, 3).unwrap(), Some(vec![1, 2, 4, 5]));
+        assert_eq!(replay_component_data(&entity, &component, &history, 4).unwrap(), None);
+
+        // Writes can't be applied without the bytes before them
+        assert!(matches!(replay_component_data(&entity, &component, &history[2..], 3), Err(SdkError::MissingComponentData(..))));
+        let unordered = [logged(13, vec![]), logged(12, vec![])];
+        assert!(matches!(replay_component_data(&entity, &component, &unordered, 3), Err(SdkError::UnorderedHistory(12, 13))));
+    }
+}
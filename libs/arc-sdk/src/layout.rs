@@ -0,0 +1,145 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, Discriminator};
+use core_ds::account::*;
+use core_ds::context::{get_latest_layout_version, get_layout_version};
+
+use crate::error::*;
+
+// Only CoreDS layouts can be told apart from the account alone, Registry accounts can need other accounts to detect theirs
+pub fn is_core_ds_account(data: &[u8]) -> bool {
+    let discriminator = match data.get(..8) {
+        Some(discriminator) => discriminator,
+        None => return false,
+    };
+    return [
+        RegistryInstance::discriminator(),
+        Entity::discriminator(),
+        ARCNFT::discriminator(),
+        EntityARCNFT::discriminator(),
+        EntityRelations::discriminator(),
+        ComponentData::discriminator(),
+        ComponentUpload::discriminator(),
+        CompressedTree::discriminator(),
+    ].iter().any(|known| known == discriminator);
+}
+
+/**
+ * Account data as it would look after migrate_account, so every supported CoreDS layout decodes with the latest structs.
+ * Any other account is returned unchanged.
+ */
+pub fn upgrade_layout(address: &Pubkey, data: &[u8]) -> Result<Vec<u8>> {
+    if !is_core_ds_account(data) {
+        return Ok(data.to_vec());
+    }
+    let version = get_layout_version(address, data)?;
+    if version > get_latest_layout_version(&data[..8]) {
+        return Err(SdkError::UnsupportedVersion(*address, version));
+    }
+    Ok(core_ds::context::upgrade_layout(address, data)?)
+}
+
+pub fn decode_account<T: AccountDeserialize>(address: &Pubkey, data: &[u8]) -> Result<T> {
+    let data = upgrade_layout(address, data)?;
+    Ok(T::try_deserialize(&mut data.as_slice())?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::{AccountSerialize, AnchorSerialize};
+    use core_ds::constant::{ENTITY_LAYOUT_VERSION, SEEDS_ENTITY_PREFIX, SEEDS_REGISTRYINSTANCE_PREFIX};
+    use core_ds::state::SerializedComponent;
+    use registry::account::RegistryConfig;
+    use std::collections::{BTreeMap, HashMap};
+
+    use crate::fetcher::fetch_account;
+
+    fn entity_address(entity_id: u64, instance: u64, registry: &Pubkey) -> Pubkey {
+        let registry_instance = Pubkey::find_program_address(&[SEEDS_REGISTRYINSTANCE_PREFIX, registry.as_ref(), instance.to_be_bytes().as_ref()], &core_ds::ID).0;
+        Pubkey::find_program_address(&[SEEDS_ENTITY_PREFIX, entity_id.to_be_bytes().as_ref(), registry_instance.as_ref()], &core_ds::ID).0
+    }
+
+    fn legacy_entity(registry: Pubkey, components: BTreeMap<Pubkey, SerializedComponent>) -> Vec<u8> {
+        let mut data = Entity::discriminator().to_vec();
+        EntityV1 { version: 1, entity_id: 7, instance: 3, registry, components }.serialize(&mut data).unwrap();
+        data
+    }
+
+    fn components() -> BTreeMap<Pubkey, SerializedComponent> {
+        BTreeMap::from([
+            (Pubkey::new_unique(), SerializedComponent { max_size: 8, data: vec![1, 2] }),
+            (Pubkey::new_unique(), SerializedComponent { max_size: 4, data: vec![3] }),
+        ])
+    }
+
+    fn check_upgraded(entity: &Entity, registry: &Pubkey, components: &BTreeMap<Pubkey, SerializedComponent>) {
+        assert_eq!(entity.version, ENTITY_LAYOUT_VERSION);
+        assert_eq!((entity.entity_id, entity.instance, entity.registry), (7, 3, *registry));
+        assert_eq!(entity.revision, 0);
+        assert_eq!(entity.components.len(), components.len());
+        for (key, component) in components {
+            assert_eq!(entity.components[key].data, component.data);
+            assert_eq!(entity.components[key].max_size, component.max_size);
+            assert_eq!(entity.component_revisions[key], 0);
+        }
+    }
+
+    #[test]
+    fn upgrades_entities_from_every_layout() {
+        let registry = Pubkey::new_unique();
+        let address = entity_address(7, 3, &registry);
+        let components = components();
+
+        let v1 = legacy_entity(registry, components.clone());
+        let mut v0 = v1.clone();
+        v0.remove(8);
+
+        assert_eq!(get_layout_version(&address, &v0).unwrap(), 0);
+        assert_eq!(get_layout_version(&address, &v1).unwrap(), 1);
+        for data in [&v0, &v1] {
+            check_upgraded(&decode_account::<Entity>(&address, data).unwrap(), &registry, &components);
+        }
+        assert_eq!(upgrade_layout(&address, &v0).unwrap(), upgrade_layout(&address, &v1).unwrap());
+
+        // The latest layout is left as it is
+        let v2 = upgrade_layout(&address, &v1).unwrap();
+        assert_eq!(get_layout_version(&address, &v2).unwrap(), ENTITY_LAYOUT_VERSION);
+        assert_eq!(upgrade_layout(&address, &v2).unwrap(), v2);
+    }
+
+    #[test]
+    fn keeps_reserved_entity_space() {
+        let registry = Pubkey::new_unique();
+        let address = entity_address(7, 3, &registry);
+        let mut v1 = legacy_entity(registry, components());
+        v1.extend_from_slice(&[0; 16]);
+
+        let upgraded = upgrade_layout(&address, &v1).unwrap();
+        let mut exact = vec![];
+        decode_account::<Entity>(&address, &v1).unwrap().try_serialize(&mut exact).unwrap();
+        assert_eq!(upgraded.len(), exact.len() + 16);
+    }
+
+    #[test]
+    fn rejects_newer_layouts() {
+        let registry = Pubkey::new_unique();
+        let address = entity_address(7, 3, &registry);
+        let mut data = legacy_entity(registry, components());
+        data[8] = ENTITY_LAYOUT_VERSION + 1;
+
+        assert!(matches!(upgrade_layout(&address, &data), Err(SdkError::UnsupportedVersion(_, version)) if version == ENTITY_LAYOUT_VERSION + 1));
+    }
+
+    #[test]
+    fn passes_other_accounts_through() {
+        let address = Pubkey::new_unique();
+        let config = RegistryConfig { version: 1, core_ds: core_ds::ID, components: 5 };
+        let mut data = vec![];
+        config.try_serialize(&mut data).unwrap();
+
+        assert_eq!(upgrade_layout(&address, &data).unwrap(), data);
+        let fetcher = HashMap::from([(address, data)]);
+        let fetched = fetch_account::<RegistryConfig>(&fetcher, &address).unwrap();
+        assert_eq!((fetched.version, fetched.core_ds, fetched.components), (1, core_ds::ID, 5));
+    }
+}
@@ -0,0 +1,17 @@
+//! Off chain counterpart to the ARC programs: decoding accounts and
+//! reassembling what the programs split across several accounts.
+
+// SdkError wraps Anchor's Error, which is large by design, and explicit returns are the house style
+#![allow(clippy::result_large_err)]
+#![allow(clippy::needless_return)]
+
+pub mod bundle;
+pub mod component;
+pub mod compressed;
+pub mod error;
+pub mod fetcher;
+pub mod history;
+pub mod layout;
+
+pub use error::{SdkError, Result};
+pub use fetcher::AccountFetcher;
@@ -1,10 +1,11 @@
 use anchor_lang::prelude::*;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use crate::state::*;
 
 #[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
 #[account]
 pub struct RegistryInstance {
+    pub version: u8,
     pub registry: Pubkey,
     pub instance: u64,
     pub entities: u64,
@@ -12,39 +13,153 @@ pub struct RegistryInstance {
 
 impl MaxSize for RegistryInstance {
     fn get_max_size() -> u64 {
-        return 32 + 8 + 8;
+        return 1 + 32 + 8 + 8;
     }
 }
 
 #[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
 #[account]
 pub struct Entity {
+    pub version: u8,
     pub entity_id: u64,
     pub instance: u64,
     pub registry: Pubkey,
+    pub revision: u64, // Goes up by one on every mutation
     pub components: BTreeMap<Pubkey, SerializedComponent>,
+    pub component_revisions: BTreeMap<Pubkey, u64>, // Entity revision each component last changed at
+}
+
+impl Entity {
+    // Called after every change to the components, removed components drop their revision
+    pub fn bump_revision(&mut self, changed: &[Pubkey]) {
+        self.revision += 1;
+        for component in changed {
+            if self.components.contains_key(component) {
+                self.component_revisions.insert(*component, self.revision);
+            } else {
+                self.component_revisions.remove(component);
+            }
+        }
+    }
 }
 
 impl MaxSize for Entity {
     fn get_max_size() -> u64 {
-        // Max size is listed with empty BTreeMap (4) with the expecation that it'll get realloc'd with new components
-        return 8+8+32+4; 
+        // Max size is listed with empty BTreeMaps (4) with the expecation that it'll get realloc'd with new components
+        return 1+8+8+32+8+4+4;
     }
 }
 
+// Entity layout 1, before revisions. Only read to migrate old Entities
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct EntityV1 {
+    pub version: u8,
+    pub entity_id: u64,
+    pub instance: u64,
+    pub registry: Pubkey,
+    pub components: BTreeMap<Pubkey, SerializedComponent>,
+}
+
 #[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
 #[account] 
 pub struct ARCNFT {
+    pub version: u8,
     pub entity: Pubkey,
     pub mint: Pubkey,
 }
 
 impl MaxSize for ARCNFT {
     fn get_max_size() -> u64 {
-        return 32 + 32;
+        return 1 + 32 + 32;
+    }
+}
+
+// Reverse index so an Entity can only ever be entangled with a single mint
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[account]
+pub struct EntityARCNFT {
+    pub version: u8,
+    pub arcnft: Pubkey,
+    pub mint: Pubkey,
+}
+
+impl MaxSize for EntityARCNFT {
+    fn get_max_size() -> u64 {
+        return 1 + 32 + 32;
     }
 }
 
 pub trait MaxSize {
     fn get_max_size() -> u64;
-}
\ No newline at end of file
+}
+
+// Links between Entities. An Entity can't be closed while this account exists
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[account]
+pub struct EntityRelations {
+    pub version: u8,
+    pub entity: Pubkey,
+    pub parent: Option<Pubkey>,
+    pub children: BTreeSet<Pubkey>,
+    pub references: BTreeMap<Pubkey, u16>,
+    pub referenced_by: u64,
+}
+
+impl EntityRelations {
+    pub fn is_empty(&self) -> bool {
+        return self.parent.is_none() && self.children.is_empty() && self.references.is_empty() && self.referenced_by == 0;
+    }
+}
+
+impl MaxSize for EntityRelations {
+    fn get_max_size() -> u64 {
+        // Children and References get realloc'd as they are attached
+        return 1 + 32 + (1+32) + 4 + 4 + 8;
+    }
+}
+
+// Holds the bytes of a component too large for the Entity, raw data follows the header at COMPONENT_BYTES_OFFSET
+// The Entity's SerializedComponent for it holds this account's address instead of the data
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[account]
+pub struct ComponentData {
+    pub header: ComponentBytesHeader,
+}
+
+impl MaxSize for ComponentData {
+    fn get_max_size() -> u64 {
+        // Data is realloc'd in as it's written
+        return ComponentBytesHeader::get_max_size();
+    }
+}
+
+// Staging area for a component uploaded in chunks, raw data follows the header at COMPONENT_BYTES_OFFSET
+// Nothing is visible on the Entity until the upload is committed
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[account]
+pub struct ComponentUpload {
+    pub header: ComponentBytesHeader,
+}
+
+impl MaxSize for ComponentUpload {
+    fn get_max_size() -> u64 {
+        // Allocated with max_size bytes of data on top
+        return ComponentBytesHeader::get_max_size();
+    }
+}
+
+// Concurrent Merkle tree of compressed Entities. The Registry Instance PDA is the tree's authority
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[account]
+pub struct CompressedTree {
+    pub version: u8,
+    pub registry_instance: Pubkey,
+    pub merkle_tree: Pubkey,
+    pub leaves: u64, // Next leaf index, CoreDS only ever appends
+}
+
+impl MaxSize for CompressedTree {
+    fn get_max_size() -> u64 {
+        return 1 + 32 + 32 + 8;
+    }
+}
@@ -1,4 +1,23 @@
-pub const SERIALIZED_COMPONENT_EXTRA_SPACE:u64 = 44;
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::pubkey;
+
+// Key, max_size and data length prefix of a component on an Entity, plus its entry in component_revisions
+pub const SERIALIZED_COMPONENT_EXTRA_SPACE:u64 = 32 + 8 + 4 + (32 + 8);
+// Layout version written to every account, accounts created before versioning read as 0
+pub const LAYOUT_VERSION:u8 = 1;
+// Entities moved to layout 2 when revisions were added
+pub const ENTITY_LAYOUT_VERSION:u8 = 2;
 pub const SEEDS_REGISTRYINSTANCE_PREFIX:&[u8;8] = b"registry";
 pub const SEEDS_ENTITY_PREFIX:&[u8;6] = b"entity";
-pub const SEEDS_ARCNFT_PREFIX:&[u8;6] = b"arcnft";
\ No newline at end of file
+pub const SEEDS_ARCNFT_PREFIX:&[u8;6] = b"arcnft";
+pub const SEEDS_ENTITY_ARCNFT_PREFIX:&[u8;13] = b"entity_arcnft";
+pub const SEEDS_ENTITY_RELATIONS_PREFIX:&[u8;16] = b"entity_relations";
+pub const SEEDS_COMPONENT_DATA_PREFIX:&[u8;14] = b"component_data";
+// ComponentData and ComponentUpload bytes start right after the discriminator and ComponentBytesHeader
+pub const COMPONENT_BYTES_OFFSET:usize = 8 + 1 + 32 + 32 + 8 + 8;
+// max_size marking a component as the address of its ComponentData account, no inline component can reserve this much
+pub const OVERFLOW_REFERENCE_MAX_SIZE:u64 = u64::MAX;
+pub const SEEDS_COMPRESSED_TREE_PREFIX:&[u8;15] = b"compressed_tree";
+pub const SEEDS_COMPONENT_UPLOAD_PREFIX:&[u8;16] = b"component_upload";
+// Metaplex Token Metadata, whose Master Editions take over mint authority of the NFTs they wrap
+pub const TOKEN_METADATA_PROGRAM_ID:Pubkey = pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
\ No newline at end of file
@@ -1,10 +1,17 @@
 use anchor_lang::prelude::*;
+use anchor_lang::{Discriminator, Event};
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::solana_program::system_instruction::MAX_PERMITTED_DATA_LENGTH;
+use anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
 use anchor_spl::token::Mint;
-use std::collections::BTreeMap;
+use spl_account_compression::{Noop, program::SplAccountCompression};
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::account::*;
 use crate::state::*;
 use crate::constant::*;
+use crate::error::*;
+use crate::event::*;
 
 #[derive(Accounts)]
 #[instruction(registry:Pubkey, instance:u64)]
@@ -40,6 +47,10 @@ pub struct InitEntity<'info>{
     pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
 
+    #[account(
+        mut,
+        constraint = registry_instance.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
     pub registry_instance: Account<'info, RegistryInstance>,
 
     #[account(
@@ -55,11 +66,63 @@ pub struct InitEntity<'info>{
     )]
     pub entity: Box<Account<'info, Entity>>,
 
+    /// CHECK: Only logged in the Entity's history, the Registry vouches for it
+    pub action_bundle: UncheckedAccount<'info>,
+
     // Only the Entity's Registry can make changes to the Entity
     #[account(
         owner = registry_instance.registry.key()
     )]
-    pub registry_signer: Signer<'info>
+    pub registry_signer: Signer<'info>,
+
+    // Mutation records are logged through the Noop program, plain logs can be truncated
+    pub noop_program: Program<'info, Noop>
+}
+
+#[derive(Accounts)]
+#[instruction(entity_id:u64, filter: Option<Vec<Pubkey>>)]
+pub struct CloneEntity<'info>{
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        constraint = source.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub source: Box<Account<'info, Entity>>,
+
+    // Clones can go to any instance of the source's Registry
+    #[account(
+        mut,
+        constraint = registry_instance.registry.key() == source.registry.key() @ CloneError::RegistryMismatch,
+        constraint = registry_instance.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_instance: Account<'info, RegistryInstance>,
+
+    #[account(
+        init,
+        payer=payer,
+        space=8+Entity::get_max_size() as usize+compute_comp_arr_max_size(&filter_components(&source.components, &filter).values().cloned().collect()),
+        seeds = [
+            SEEDS_ENTITY_PREFIX,
+            entity_id.to_be_bytes().as_ref(),
+            registry_instance.key().as_ref()
+        ],
+        bump,
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+
+    /// CHECK: Only logged in the Entity's history, the Registry vouches for it
+    pub action_bundle: UncheckedAccount<'info>,
+
+    // Only the Entity's Registry can make changes to the Entity
+    #[account(
+        owner = registry_instance.registry.key()
+    )]
+    pub registry_signer: Signer<'info>,
+
+    // Mutation records are logged through the Noop program, plain logs can be truncated
+    pub noop_program: Program<'info, Noop>
 }
 
 #[derive(Accounts)]
@@ -68,30 +131,104 @@ pub struct MintARCNFT<'info>{
     pub payer: Signer<'info>,
     pub system_program: Program<'info, System>,
 
+    #[account(
+        constraint = registry_instance.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
     pub registry_instance: Account<'info, RegistryInstance>,
+    #[account(
+        constraint = entity.registry.key() == registry_instance.registry.key() && entity.instance == registry_instance.instance @ ARCNFTError::EntityInstanceMismatch,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
     pub entity: Box<Account<'info, Entity>>,
+
+    // Mint has to behave like an NFT: no fractions, at most one token, and nobody outside the minter (or a Metaplex Master Edition) can inflate it
+    #[account(
+        constraint = mint.decimals == 0 @ ARCNFTError::InvalidMintDecimals,
+        constraint = mint.supply <= 1 @ ARCNFTError::InvalidMintSupply,
+        constraint = check_mint_authority(&mint, &mint_authority.key()) @ ARCNFTError::UncontrolledMintAuthority,
+    )]
     pub mint: Account<'info, Mint>,
+    // Only allowed to still hold the mint authority if it's the one asking for the ARCNFT
+    pub mint_authority: Signer<'info>,
 
     #[account(
         init,
         payer=payer,
         space=8+ARCNFT::get_max_size() as usize,
         seeds=[
-            b"arcnft",
-            mint.key().as_ref(),
-            entity.key().as_ref()
+            SEEDS_ARCNFT_PREFIX,
+            mint.key().as_ref()
         ],
         bump,
     )]
     pub arcnft: Account<'info, ARCNFT>,   
 
+    // Fails to init if the Entity is already entangled with another mint
+    #[account(
+        init,
+        payer=payer,
+        space=8+EntityARCNFT::get_max_size() as usize,
+        seeds=[
+            SEEDS_ENTITY_ARCNFT_PREFIX,
+            entity.key().as_ref()
+        ],
+        bump,
+    )]
+    pub entity_arcnft: Account<'info, EntityARCNFT>,
+
     // Only the Entity's Registry can make mint the NFT
     #[account(
-        seeds = [
-            SEEDS_ARCNFT_PREFIX
+        owner = registry_instance.registry.key()
+    )]
+    pub registry_signer: Signer<'info>
+}
+
+#[derive(Accounts)]
+pub struct BurnARCNFT<'info>{
+    #[account(mut)]
+    pub benefactor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+
+    // The token has to be burned before the ARCNFT can be closed
+    #[account(
+        constraint = mint.supply == 0 @ ARCNFTError::MintNotBurned
+    )]
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        close = benefactor,
+        has_one = entity,
+        has_one = mint,
+        seeds=[
+            SEEDS_ARCNFT_PREFIX,
+            mint.key().as_ref()
+        ],
+        bump,
+        constraint = arcnft.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub arcnft: Account<'info, ARCNFT>,
+
+    #[account(
+        mut,
+        close = benefactor,
+        seeds=[
+            SEEDS_ENTITY_ARCNFT_PREFIX,
+            entity.key().as_ref()
         ],
         bump,
-        seeds::program = registry_instance.registry.key()
+        constraint = entity_arcnft.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity_arcnft: Account<'info, EntityARCNFT>,
+
+    // Only the Entity's Registry can burn the NFT
+    #[account(
+        owner = entity.registry.key()
     )]
     pub registry_signer: Signer<'info>
 }
@@ -108,14 +245,21 @@ pub struct AddComponent<'info> {
         realloc = entity.to_account_info().data_len() + compute_comp_arr_max_size(&components.iter().map(|tuple| tuple.1.clone() ).collect()),
         realloc::payer = payer,
         realloc::zero = true,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub entity: Account<'info, Entity>,
 
+    /// CHECK: Only logged in the Entity's history, the Registry vouches for it
+    pub action_bundle: UncheckedAccount<'info>,
+
     // Only the Entity's registry can make changes to the Entity
     #[account(
         owner = entity.registry.key()
     )]
-    pub registry_signer: Signer<'info>
+    pub registry_signer: Signer<'info>,
+
+    // Mutation records are logged through the Noop program, plain logs can be truncated
+    pub noop_program: Program<'info, Noop>
 }
 
 #[derive(Accounts)]
@@ -130,27 +274,43 @@ pub struct RemoveComponent<'info> {
         realloc = entity.to_account_info().data_len() - get_removed_size(&entity.components, &removed_components),
         realloc::payer = benefactor,
         realloc::zero = false,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub entity: Account<'info, Entity>,
 
+    /// CHECK: Only logged in the Entity's history, the Registry vouches for it
+    pub action_bundle: UncheckedAccount<'info>,
+
     // Only the Entity's registry can make changes to the Entity
     #[account(
         owner = entity.registry.key()
     )]
-    pub registry_signer: Signer<'info>
+    pub registry_signer: Signer<'info>,
+
+    // Mutation records are logged through the Noop program, plain logs can be truncated
+    pub noop_program: Program<'info, Noop>
 }
 
 #[derive(Accounts)]
 #[instruction(components: Vec<(Pubkey, Vec<u8>)>)]
 pub struct ModifyComponent<'info> {
-    #[account(mut)]
+    #[account(
+        mut,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
     pub entity: Account<'info, Entity>,
 
+    /// CHECK: Only logged in the Entity's history, the Registry vouches for it
+    pub action_bundle: UncheckedAccount<'info>,
+
     // Only the Entity's registry can make changes to the Entity
     #[account(
         owner = entity.registry.key()
     )]
-    pub registry_signer: Signer<'info>
+    pub registry_signer: Signer<'info>,
+
+    // Mutation records are logged through the Noop program, plain logs can be truncated
+    pub noop_program: Program<'info, Noop>
 }
 
 #[derive(Accounts)]
@@ -161,10 +321,35 @@ pub struct RemoveEntity<'info>{
     
     #[account(
         mut,
-        close = benefactor
+        close = benefactor,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub entity: Account<'info, Entity>,
 
+    // Entities entangled with a mint have to burn their ARCNFT first
+    /// CHECK: Only checked to be empty
+    #[account(
+        seeds=[
+            SEEDS_ENTITY_ARCNFT_PREFIX,
+            entity.key().as_ref()
+        ],
+        bump,
+        constraint = entity_arcnft.data_is_empty() @ ARCNFTError::EntityHasARCNFT
+    )]
+    pub entity_arcnft: AccountInfo<'info>,
+
+    // Entities with relations have to detach and close them first
+    /// CHECK: Only checked to be empty
+    #[account(
+        seeds=[
+            SEEDS_ENTITY_RELATIONS_PREFIX,
+            entity.key().as_ref()
+        ],
+        bump,
+        constraint = entity_relations.data_is_empty() @ RelationError::EntityHasRelations
+    )]
+    pub entity_relations: AccountInfo<'info>,
+
     // Only the Entity's registry can make changes to the Entity
     #[account(
         owner = entity.registry.key()
@@ -172,19 +357,1086 @@ pub struct RemoveEntity<'info>{
     pub registry_signer: Signer<'info>
 }
 
-/************************************************ Utility Functions */
-pub fn compute_comp_arr_max_size(components: &Vec<SerializedComponent>) -> usize {
-    let mut max_size:usize = 0;
-    for comp in components {
-        max_size += comp.max_size as usize + SERIALIZED_COMPONENT_EXTRA_SPACE as usize;
-    }
-    return max_size;
+#[derive(Accounts)]
+pub struct InitRelations<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Account<'info, Entity>,
+
+    #[account(
+        init,
+        payer=payer,
+        seeds=[
+            SEEDS_ENTITY_RELATIONS_PREFIX,
+            entity.key().as_ref()
+        ],
+        bump,
+        space=8+EntityRelations::get_max_size() as usize
+    )]
+    pub entity_relations: Account<'info, EntityRelations>,
+
+    // Only the Entity's registry can make changes to the Entity
+    #[account(
+        owner = entity.registry.key()
+    )]
+    pub registry_signer: Signer<'info>
 }
 
-pub fn get_removed_size(components: &BTreeMap<Pubkey, SerializedComponent>, removed_components: &Vec<Pubkey>) -> usize {
-    let mut removed_size:usize = 0;
-    for comp in removed_components {
-        removed_size += components.get(comp).unwrap().max_size as usize + SERIALIZED_COMPONENT_EXTRA_SPACE as usize;
-    }
-    return removed_size;
-}
\ No newline at end of file
+#[derive(Accounts)]
+#[instruction(relation: Relation)]
+pub struct Attach<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        constraint = entity.key() != target.key() @ RelationError::SelfRelation,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+    #[account(
+        constraint = target.registry.key() == entity.registry.key() @ RelationError::RegistryMismatch,
+        constraint = target.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub target: Box<Account<'info, Entity>>,
+
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_ENTITY_RELATIONS_PREFIX,
+            entity.key().as_ref()
+        ],
+        bump,
+        realloc = entity_relations.to_account_info().data_len() + relation.entity_size(),
+        realloc::payer = payer,
+        realloc::zero = true,
+        constraint = entity_relations.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity_relations: Box<Account<'info, EntityRelations>>,
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_ENTITY_RELATIONS_PREFIX,
+            target.key().as_ref()
+        ],
+        bump,
+        realloc = target_relations.to_account_info().data_len() + relation.target_size(),
+        realloc::payer = payer,
+        realloc::zero = true,
+        constraint = target_relations.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub target_relations: Box<Account<'info, EntityRelations>>,
+
+    // Only the Entity's registry can make changes to the Entity
+    #[account(
+        owner = entity.registry.key()
+    )]
+    pub registry_signer: Signer<'info>
+}
+
+#[derive(Accounts)]
+#[instruction(relation: Relation)]
+pub struct Detach<'info> {
+    #[account(mut)]
+    pub benefactor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+    #[account(
+        constraint = target.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub target: Box<Account<'info, Entity>>,
+
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_ENTITY_RELATIONS_PREFIX,
+            entity.key().as_ref()
+        ],
+        bump,
+        realloc = entity_relations.to_account_info().data_len() - relation.entity_size(),
+        realloc::payer = benefactor,
+        realloc::zero = false,
+        constraint = entity_relations.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity_relations: Box<Account<'info, EntityRelations>>,
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_ENTITY_RELATIONS_PREFIX,
+            target.key().as_ref()
+        ],
+        bump,
+        realloc = target_relations.to_account_info().data_len() - relation.target_size(),
+        realloc::payer = benefactor,
+        realloc::zero = false,
+        constraint = target_relations.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub target_relations: Box<Account<'info, EntityRelations>>,
+
+    // Only the Entity's registry can make changes to the Entity
+    #[account(
+        owner = entity.registry.key()
+    )]
+    pub registry_signer: Signer<'info>
+}
+
+#[derive(Accounts)]
+pub struct CloseRelations<'info> {
+    #[account(mut)]
+    pub benefactor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Account<'info, Entity>,
+
+    #[account(
+        mut,
+        close = benefactor,
+        seeds=[
+            SEEDS_ENTITY_RELATIONS_PREFIX,
+            entity.key().as_ref()
+        ],
+        bump,
+        constraint = entity_relations.is_empty() @ RelationError::RelationsNotEmpty,
+        constraint = entity_relations.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity_relations: Account<'info, EntityRelations>,
+
+    // Only the Entity's registry can make changes to the Entity
+    #[account(
+        owner = entity.registry.key()
+    )]
+    pub registry_signer: Signer<'info>
+}
+
+// Entity is realloc'd once to the net size in the instruction
+#[derive(Accounts)]
+pub struct MutateComponents<'info> {
+    // Pays for growing the Entity and gets refunded if it shrinks
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        mut,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Account<'info, Entity>,
+
+    /// CHECK: Only logged in the Entity's history, the Registry vouches for it
+    pub action_bundle: UncheckedAccount<'info>,
+
+    // Only the Entity's registry can make changes to the Entity
+    #[account(
+        owner = entity.registry.key()
+    )]
+    pub registry_signer: Signer<'info>,
+
+    // Mutation records are logged through the Noop program, plain logs can be truncated
+    pub noop_program: Program<'info, Noop>
+}
+
+#[derive(Accounts)]
+#[instruction(component: Pubkey, max_size: u64)]
+pub struct AddOverflowComponent<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    // Only grows by the reference to the ComponentData account
+    #[account(
+        mut,
+        constraint = !entity.components.contains_key(&component) @ OverflowError::ComponentExists,
+        realloc = entity.to_account_info().data_len() + get_overflow_reference_size(),
+        realloc::payer = payer,
+        realloc::zero = true,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+
+    #[account(
+        init,
+        payer=payer,
+        seeds=[
+            SEEDS_COMPONENT_DATA_PREFIX,
+            entity.key().as_ref(),
+            component.as_ref()
+        ],
+        bump,
+        constraint = max_size as usize <= MAX_PERMITTED_DATA_LENGTH as usize - COMPONENT_BYTES_OFFSET @ OverflowError::MaxSizeTooLarge,
+        space=8+ComponentData::get_max_size() as usize
+    )]
+    pub component_data: Account<'info, ComponentData>,
+
+    /// CHECK: Only logged in the Entity's history, the Registry vouches for it
+    pub action_bundle: UncheckedAccount<'info>,
+
+    // Only the Entity's registry can make changes to the Entity
+    #[account(
+        owner = entity.registry.key()
+    )]
+    pub registry_signer: Signer<'info>,
+
+    // Mutation records are logged through the Noop program, plain logs can be truncated
+    pub noop_program: Program<'info, Noop>
+}
+
+#[derive(Accounts)]
+#[instruction(component: Pubkey, offset: u64, data: Vec<u8>)]
+pub struct WriteComponentData<'info> {
+    // Pays for growing the ComponentData account as it's written
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    // Writes bump the Entity's revision
+    #[account(
+        mut,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_COMPONENT_DATA_PREFIX,
+            entity.key().as_ref(),
+            component.as_ref()
+        ],
+        bump,
+        constraint = offset.checked_add(data.len() as u64).is_some_and(|end| end <= component_data.header.max_size) @ OverflowError::WriteOutOfBounds,
+        constraint = component_data.header.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub component_data: Account<'info, ComponentData>,
+
+    /// CHECK: Only logged in the Entity's history, the Registry vouches for it
+    pub action_bundle: UncheckedAccount<'info>,
+
+    // Only the Entity's registry can make changes to the Entity
+    #[account(
+        owner = entity.registry.key()
+    )]
+    pub registry_signer: Signer<'info>,
+
+    // Mutation records are logged through the Noop program, plain logs can be truncated
+    pub noop_program: Program<'info, Noop>
+}
+
+// Also cleans up ComponentData left behind if the reference was removed with remove_component
+#[derive(Accounts)]
+#[instruction(component: Pubkey)]
+pub struct RemoveOverflowComponent<'info> {
+    #[account(mut)]
+    pub benefactor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        mut,
+        realloc = entity.to_account_info().data_len() - if entity.components.contains_key(&component) { get_overflow_reference_size() } else { 0 },
+        realloc::payer = benefactor,
+        realloc::zero = false,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+
+    #[account(
+        mut,
+        close = benefactor,
+        seeds=[
+            SEEDS_COMPONENT_DATA_PREFIX,
+            entity.key().as_ref(),
+            component.as_ref()
+        ],
+        bump,
+        constraint = component_data.header.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub component_data: Account<'info, ComponentData>,
+
+    /// CHECK: Only logged in the Entity's history, the Registry vouches for it
+    pub action_bundle: UncheckedAccount<'info>,
+
+    // Only the Entity's registry can make changes to the Entity
+    #[account(
+        owner = entity.registry.key()
+    )]
+    pub registry_signer: Signer<'info>,
+
+    // Mutation records are logged through the Noop program, plain logs can be truncated
+    pub noop_program: Program<'info, Noop>
+}
+
+#[derive(Accounts)]
+#[instruction(component: Pubkey, max_size: u64)]
+pub struct AllocateComponent<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+
+    // Commit reallocs the Entity by the whole component at once
+    #[account(
+        init,
+        payer=payer,
+        seeds=[
+            SEEDS_COMPONENT_UPLOAD_PREFIX,
+            entity.key().as_ref(),
+            component.as_ref()
+        ],
+        bump,
+        constraint = max_size as usize + SERIALIZED_COMPONENT_EXTRA_SPACE as usize <= MAX_PERMITTED_DATA_INCREASE @ UploadError::MaxSizeTooLarge,
+        space=8+ComponentUpload::get_max_size() as usize+max_size as usize
+    )]
+    pub component_upload: Account<'info, ComponentUpload>,
+
+    // Only the Entity's registry can make changes to the Entity
+    #[account(
+        owner = entity.registry.key()
+    )]
+    pub registry_signer: Signer<'info>
+}
+
+#[derive(Accounts)]
+#[instruction(component: Pubkey, offset: u64, data: Vec<u8>)]
+pub struct WriteComponentChunk<'info> {
+    #[account(
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_COMPONENT_UPLOAD_PREFIX,
+            entity.key().as_ref(),
+            component.as_ref()
+        ],
+        bump,
+        constraint = offset.checked_add(data.len() as u64).is_some_and(|end| end <= component_upload.header.max_size) @ UploadError::WriteOutOfBounds,
+        constraint = component_upload.header.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub component_upload: Account<'info, ComponentUpload>,
+
+    // Only the Entity's registry can make changes to the Entity
+    #[account(
+        owner = entity.registry.key()
+    )]
+    pub registry_signer: Signer<'info>
+}
+
+#[derive(Accounts)]
+#[instruction(component: Pubkey)]
+pub struct CommitComponent<'info> {
+    // Pays for growing the Entity and gets the upload's rent back
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        mut,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+
+    #[account(
+        mut,
+        close = payer,
+        seeds=[
+            SEEDS_COMPONENT_UPLOAD_PREFIX,
+            entity.key().as_ref(),
+            component.as_ref()
+        ],
+        bump,
+        constraint = component_upload.header.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub component_upload: Account<'info, ComponentUpload>,
+
+    /// CHECK: Only logged in the Entity's history, the Registry vouches for it
+    pub action_bundle: UncheckedAccount<'info>,
+
+    // Only the Entity's registry can make changes to the Entity
+    #[account(
+        owner = entity.registry.key()
+    )]
+    pub registry_signer: Signer<'info>,
+
+    // Mutation records are logged through the Noop program, plain logs can be truncated
+    pub noop_program: Program<'info, Noop>
+}
+
+#[derive(Accounts)]
+#[instruction(component: Pubkey)]
+pub struct CancelComponent<'info> {
+    #[account(mut)]
+    pub benefactor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+
+    #[account(
+        mut,
+        close = benefactor,
+        seeds=[
+            SEEDS_COMPONENT_UPLOAD_PREFIX,
+            entity.key().as_ref(),
+            component.as_ref()
+        ],
+        bump,
+        constraint = component_upload.header.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub component_upload: Account<'info, ComponentUpload>,
+
+    // Only the Entity's registry can make changes to the Entity
+    #[account(
+        owner = entity.registry.key()
+    )]
+    pub registry_signer: Signer<'info>
+}
+
+#[derive(Accounts)]
+#[instruction(max_depth: u32, max_buffer_size: u32)]
+pub struct InitCompressedTree<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    // Signs for the tree
+    #[account(
+        seeds=[
+            SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry_instance.registry.key().to_bytes().as_ref(),
+            registry_instance.instance.to_be_bytes().as_ref()
+        ],
+        bump,
+        constraint = registry_instance.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_instance: Account<'info, RegistryInstance>,
+
+    #[account(
+        init,
+        payer=payer,
+        seeds=[
+            SEEDS_COMPRESSED_TREE_PREFIX,
+            merkle_tree.key().as_ref()
+        ],
+        bump,
+        space=8+CompressedTree::get_max_size() as usize
+    )]
+    pub compressed_tree: Account<'info, CompressedTree>,
+
+    /// CHECK: Allocated by the client and owned by SPL Account Compression, which initalizes it
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    // Only the Instance's Registry can make changes to the Instance
+    #[account(
+        owner = registry_instance.registry.key()
+    )]
+    pub registry_signer: Signer<'info>,
+
+    pub compression_program: Program<'info, SplAccountCompression>,
+    pub noop_program: Program<'info, Noop>,
+}
+
+// Proof nodes for the leaf being replaced are passed in through remaining_accounts
+#[derive(Accounts)]
+pub struct CompressedEntities<'info> {
+    // Signs for the tree
+    #[account(
+        seeds=[
+            SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry_instance.registry.key().to_bytes().as_ref(),
+            registry_instance.instance.to_be_bytes().as_ref()
+        ],
+        bump,
+        constraint = registry_instance.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_instance: Account<'info, RegistryInstance>,
+
+    #[account(
+        mut,
+        seeds=[
+            SEEDS_COMPRESSED_TREE_PREFIX,
+            merkle_tree.key().as_ref()
+        ],
+        bump,
+        has_one = registry_instance,
+        has_one = merkle_tree,
+        constraint = compressed_tree.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub compressed_tree: Account<'info, CompressedTree>,
+
+    /// CHECK: Checked by SPL Account Compression
+    #[account(mut)]
+    pub merkle_tree: UncheckedAccount<'info>,
+
+    // Only the Instance's Registry can make changes to its Entities
+    #[account(
+        owner = registry_instance.registry.key()
+    )]
+    pub registry_signer: Signer<'info>,
+
+    pub compression_program: Program<'info, SplAccountCompression>,
+    pub noop_program: Program<'info, Noop>,
+}
+
+// Entities are passed in through remaining_accounts, one per change set
+#[derive(Accounts)]
+pub struct BatchEntities<'info>{
+    // Pays for growing Entities and gets refunded for shrinking them
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Only logged in the Entity's history, the Registry vouches for it
+    pub action_bundle: UncheckedAccount<'info>,
+
+    // Checked against every Entity's registry when the batch is loaded
+    pub registry_signer: Signer<'info>,
+
+    // Mutation records are logged through the Noop program, plain logs can be truncated
+    pub noop_program: Program<'info, Noop>
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info>{
+    // Pays for the extra layout bytes
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Any CoreDS account, its type and layout version are detected from its data
+    #[account(
+        mut,
+        owner = crate::ID
+    )]
+    pub account: UncheckedAccount<'info>,
+}
+
+/************************************************ Utility Functions */
+pub fn compute_comp_arr_max_size(components: &Vec<SerializedComponent>) -> usize {
+    let mut max_size:usize = 0;
+    for comp in components {
+        max_size += comp.get_space() + SERIALIZED_COMPONENT_EXTRA_SPACE as usize;
+    }
+    return max_size;
+}
+
+// Runs in the realloc constraint before the handler, so unknown components count as nothing and are rejected in the handler. Repeats only count once
+pub fn get_removed_size(components: &BTreeMap<Pubkey, SerializedComponent>, removed_components: &[Pubkey]) -> usize {
+    let mut removed_size:usize = 0;
+    for comp in removed_components.iter().collect::<BTreeSet<&Pubkey>>() {
+        if let Some(removed) = components.get(comp) {
+            removed_size += removed.get_space() + SERIALIZED_COMPONENT_EXTRA_SPACE as usize;
+        }
+    }
+    return removed_size;
+}
+
+// Components to copy from an Entity, all of them when there's no filter
+pub fn filter_components(components: &BTreeMap<Pubkey, SerializedComponent>, filter: &Option<Vec<Pubkey>>) -> BTreeMap<Pubkey, SerializedComponent> {
+    match filter {
+        None => components.clone(),
+        Some(keys) => components.iter()
+            .filter(|(key, _)| keys.contains(key))
+            .map(|(key, comp)| (*key, comp.clone()))
+            .collect(),
+    }
+}
+
+pub fn get_components_size(components: &BTreeMap<Pubkey, SerializedComponent>) -> usize {
+    return compute_comp_arr_max_size(&components.values().cloned().collect());
+}
+
+// Adds or overwrites inline components, the Entity has to be realloc'd for them already
+pub fn add_entity_components(entity: &mut Entity, components: Vec<(Pubkey, SerializedComponent)>) -> Result<()> {
+    for comp in components {
+        check_inline(&comp.1)?;
+        require!(comp.1.data.len() as u64 <= comp.1.max_size, ComponentError::InvalidDataLengthError);
+        if let Some(existing) = entity.components.get(&comp.0) {
+            check_inline(existing)?;
+        }
+        entity.components.insert(comp.0, comp.1);
+    }
+    Ok(())
+}
+
+// Replaces the data of inline components the Entity already has, within their max size
+pub fn modify_entity_components(entity: &mut Entity, components: Vec<(Pubkey, Vec<u8>)>) -> Result<()> {
+    for comp in components {
+        let existing = entity.components.get_mut(&comp.0).ok_or(ComponentError::ComponentNotFound)?;
+        check_inline(existing)?;
+        require!(comp.1.len() as u64 <= existing.max_size, ComponentError::InvalidDataLengthError);
+        existing.data = comp.1;
+    }
+    Ok(())
+}
+
+// Drops components the Entity has, checking all of them before removing any
+pub fn remove_entity_components(entity: &mut Entity, removed_components: &[Pubkey]) -> Result<()> {
+    for comp in removed_components.iter() {
+        require!(entity.components.contains_key(comp), ComponentError::ComponentNotFound);
+    }
+    for comp in removed_components.iter() {
+        entity.components.remove(comp);
+    }
+    Ok(())
+}
+
+// Returns the components as they'd be after every operation is applied in order
+pub fn apply_component_operations(components: &BTreeMap<Pubkey, SerializedComponent>, operations: &Vec<ComponentOperation>) -> Result<BTreeMap<Pubkey, SerializedComponent>> {
+    let mut mutated = components.clone();
+    for op in operations {
+        match op {
+            ComponentOperation::Add(component, serialized) => {
+                check_inline(serialized)?;
+                require!(serialized.data.len() as u64 <= serialized.max_size, ComponentError::InvalidDataLengthError);
+                if let Some(existing) = mutated.get(component) {
+                    check_inline(existing)?;
+                }
+                mutated.insert(*component, serialized.clone());
+            },
+            ComponentOperation::Modify(component, data) => {
+                let comp = mutated.get_mut(component).ok_or(ComponentError::ComponentNotFound)?;
+                check_inline(comp)?;
+                require!(data.len() as u64 <= comp.max_size, ComponentError::InvalidDataLengthError);
+                comp.data = data.clone();
+            },
+            ComponentOperation::Remove(component) => {
+                mutated.remove(component).ok_or(ComponentError::ComponentNotFound)?;
+            }
+        }
+    }
+    return Ok(mutated);
+}
+
+// Changed components as they were before a mutation, for record_mutation
+pub fn get_component_hashes(entity: &Entity, changed: &[Pubkey]) -> BTreeMap<Pubkey, Option<ComponentHash>> {
+    return changed.iter()
+        .map(|component| (*component, entity.components.get(component).map(ComponentHash::new)))
+        .collect();
+}
+
+// Logs the Entity's history record for a mutation through the Noop program, once the revision is bumped
+pub fn record_mutation(address: Pubkey, entity: &Entity, old: BTreeMap<Pubkey, Option<ComponentHash>>, action_bundle: Pubkey, noop_program: &Program<Noop>) -> Result<()> {
+    let record = EntityMutation {
+        entity: address,
+        revision: entity.revision,
+        slot: Clock::get()?.slot,
+        action_bundle,
+        changes: old.into_iter()
+            .map(|(component, old)| ComponentChange {
+                component,
+                old,
+                new: entity.components.get(&component).map(ComponentHash::new),
+            })
+            .collect(),
+    };
+    // Event data keeps the discriminator, so records can be told apart from the other CoreDS events
+    spl_account_compression::wrap_application_data_v1(record.data(), noop_program)
+}
+
+// Logs the bytes a write_component_data copied into ComponentData, once the revision is bumped
+pub fn record_component_data_write(address: Pubkey, entity: &Entity, component: Pubkey, offset: u64, data: Vec<u8>, noop_program: &Program<Noop>) -> Result<()> {
+    let write = ComponentDataWritten {
+        entity: address,
+        component,
+        revision: entity.revision,
+        offset,
+        data,
+    };
+    spl_account_compression::wrap_application_data_v1(write.data(), noop_program)
+}
+
+// Copies a chunk into the bytes following a ComponentBytesHeader, the account has to be large enough already
+pub fn write_component_bytes(account: &AccountInfo, header: &mut ComponentBytesHeader, offset: u64, data: &[u8], out_of_bounds: Error) -> Result<()> {
+    let end = match offset.checked_add(data.len() as u64) {
+        Some(end) if end <= header.max_size => end,
+        _ => return Err(out_of_bounds),
+    };
+    account.try_borrow_mut_data()?[COMPONENT_BYTES_OFFSET + offset as usize..COMPONENT_BYTES_OFFSET + end as usize].copy_from_slice(data);
+
+    if end > header.len {
+        header.len = end;
+    }
+    Ok(())
+}
+
+// Overflow references can be removed like any component, everything else goes through the overflow instructions
+pub fn check_inline(serialized: &SerializedComponent) -> Result<()> {
+    require!(!serialized.is_overflow_reference(), OverflowError::OverflowReference);
+    Ok(())
+}
+
+// Space an Entity needs to point a component at its ComponentData account
+pub fn get_overflow_reference_size() -> usize {
+    return 32 + SERIALIZED_COMPONENT_EXTRA_SPACE as usize;
+}
+
+pub fn check_mint_authority(mint: &Account<Mint>, mint_authority: &Pubkey) -> bool {
+    match mint.mint_authority {
+        COption::None => true,
+        COption::Some(authority) => authority == *mint_authority || authority == get_master_edition(&mint.key()),
+    }
+}
+
+pub fn get_master_edition(mint: &Pubkey) -> Pubkey {
+    return Pubkey::find_program_address(
+        &[
+            b"metadata",
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            mint.as_ref(),
+            b"edition"
+        ],
+        &TOKEN_METADATA_PROGRAM_ID
+    ).0;
+}
+
+pub fn load_batch_entities<'info>(remaining_accounts: &[AccountInfo<'info>], registry_signer: &Signer<'info>, changes: usize) -> Result<Vec<Account<'info, Entity>>> {
+    require!(remaining_accounts.len() == changes, BatchError::EntityCountMismatch);
+    let mut entities = vec![];
+    // Every copy of an account is deserialized and written back on its own, so only the last copy's changes would stick
+    let mut keys = BTreeSet::new();
+    for info in remaining_accounts {
+        require!(keys.insert(info.key()), BatchError::DuplicateEntity);
+        require!(info.is_writable, BatchError::EntityNotWritable);
+        let entity: Account<Entity> = Account::try_from(info)?;
+        // Stale layouts have to go through migrate_account first, same as the declared Entities
+        require!(entity.version == ENTITY_LAYOUT_VERSION, MigrationError::OutdatedLayout);
+        // Only the Entity's registry can make changes to the Entity
+        require_keys_eq!(entity.registry, *registry_signer.owner, BatchError::RegistryMismatch);
+        entities.push(entity);
+    }
+    return Ok(entities);
+}
+
+// Same as the realloc constraint, for accounts that can't be declared up front
+pub fn realloc_account<'info>(account: &AccountInfo<'info>, new_len: usize, payer: &AccountInfo<'info>, system_program: &AccountInfo<'info>) -> Result<()> {
+    let rent_exempt = Rent::get()?.minimum_balance(new_len);
+    let lamports = account.lamports();
+    if rent_exempt > lamports {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                }
+            ),
+            rent_exempt - lamports
+        )?;
+    } else if lamports > rent_exempt {
+        **account.try_borrow_mut_lamports()? -= lamports - rent_exempt;
+        **payer.try_borrow_mut_lamports()? += lamports - rent_exempt;
+    }
+    account.realloc(new_len, true)?;
+    Ok(())
+}
+
+/**
+ * Accounts created before layouts were versioned have no version byte and read as version 0.
+ * They're told apart exactly, by length for fixed size accounts and by re-deriving the PDA from either layout otherwise.
+ */
+pub fn get_layout_version(address: &Pubkey, data: &[u8]) -> Result<u8> {
+    let discriminator = data.get(..8).ok_or(error!(MigrationError::UnknownAccount))?;
+
+    if discriminator == RegistryInstance::discriminator() {
+        return get_fixed_layout_version(data, 32 + 8 + 8);
+    } else if discriminator == ARCNFT::discriminator() || discriminator == EntityARCNFT::discriminator() {
+        return get_fixed_layout_version(data, 32 + 32);
+    } else if discriminator == CompressedTree::discriminator() {
+        return get_fixed_layout_version(data, 32 + 32 + 8);
+    } else if discriminator == Entity::discriminator() {
+        return get_pda_layout_version(address, data, |fields| {
+            let entity_id = read_u64(fields, 0)?;
+            let instance = read_u64(fields, 8)?;
+            let registry = read_pubkey(fields, 16)?;
+            let registry_instance = Pubkey::find_program_address(&[SEEDS_REGISTRYINSTANCE_PREFIX, registry.as_ref(), instance.to_be_bytes().as_ref()], &crate::ID).0;
+            Some(Pubkey::find_program_address(&[SEEDS_ENTITY_PREFIX, entity_id.to_be_bytes().as_ref(), registry_instance.as_ref()], &crate::ID).0)
+        });
+    } else if discriminator == EntityRelations::discriminator() {
+        return get_pda_layout_version(address, data, |fields| {
+            let entity = read_pubkey(fields, 0)?;
+            Some(Pubkey::find_program_address(&[SEEDS_ENTITY_RELATIONS_PREFIX, entity.as_ref()], &crate::ID).0)
+        });
+    } else if discriminator == ComponentData::discriminator() {
+        return get_pda_layout_version(address, data, |fields| {
+            let entity = read_pubkey(fields, 0)?;
+            let component = read_pubkey(fields, 32)?;
+            Some(Pubkey::find_program_address(&[SEEDS_COMPONENT_DATA_PREFIX, entity.as_ref(), component.as_ref()], &crate::ID).0)
+        });
+    } else if discriminator == ComponentUpload::discriminator() {
+        return get_pda_layout_version(address, data, |fields| {
+            let entity = read_pubkey(fields, 0)?;
+            let component = read_pubkey(fields, 32)?;
+            Some(Pubkey::find_program_address(&[SEEDS_COMPONENT_UPLOAD_PREFIX, entity.as_ref(), component.as_ref()], &crate::ID).0)
+        });
+    }
+
+    err!(MigrationError::UnknownAccount)
+}
+
+// legacy_size is the size of the unversioned layout, without discriminator
+pub fn get_fixed_layout_version(data: &[u8], legacy_size: usize) -> Result<u8> {
+    if data.len() == 8 + legacy_size {
+        return Ok(0);
+    }
+    data.get(8).copied().ok_or(error!(MigrationError::UnknownLayout))
+}
+
+// derive gets the account's fields (everything after the discriminator and version byte) and returns the PDA they seed
+pub fn get_pda_layout_version(address: &Pubkey, data: &[u8], derive: impl Fn(&[u8]) -> Option<Pubkey>) -> Result<u8> {
+    if data.len() > 8 && derive(&data[9..]) == Some(*address) {
+        return Ok(data[8]);
+    }
+    if derive(&data[8..]) == Some(*address) {
+        return Ok(0);
+    }
+    err!(MigrationError::UnknownLayout)
+}
+
+pub fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(data.get(offset..offset + 8)?.try_into().ok()?))
+}
+
+pub fn read_pubkey(data: &[u8], offset: usize) -> Option<Pubkey> {
+    Some(Pubkey::new_from_array(data.get(offset..offset + 32)?.try_into().ok()?))
+}
+
+// Latest layout of an account type, from its discriminator
+pub fn get_latest_layout_version(discriminator: &[u8]) -> u8 {
+    if discriminator == Entity::discriminator() {
+        return ENTITY_LAYOUT_VERSION;
+    }
+    LAYOUT_VERSION
+}
+
+/**
+ * Account data converted to the latest layout of its type, one version at a time.
+ * Shared with off chain decoders so they can read accounts that haven't been migrated yet.
+ */
+pub fn upgrade_layout(address: &Pubkey, data: &[u8]) -> Result<Vec<u8>> {
+    let mut version = get_layout_version(address, data)?;
+    let mut upgraded = data.to_vec();
+    if version == 0 {
+        upgraded = insert_version_byte(&upgraded, 1);
+        version = 1;
+    }
+    if version == 1 && data[..8] == Entity::discriminator() {
+        upgraded = upgrade_entity_v1(&upgraded)?;
+        version = 2;
+    }
+    require!(version == get_latest_layout_version(&data[..8]), MigrationError::UnsupportedVersion);
+    Ok(upgraded)
+}
+
+// Version 0 -> 1, every field moves one byte down to make room for the version
+pub fn insert_version_byte(data: &[u8], version: u8) -> Vec<u8> {
+    let mut upgraded = Vec::with_capacity(data.len() + 1);
+    upgraded.extend_from_slice(&data[..8]);
+    upgraded.push(version);
+    upgraded.extend_from_slice(&data[8..]);
+    upgraded
+}
+
+// Entity 1 -> 2, existing components start at revision 0. Space the Entity had reserved past its components is kept
+fn upgrade_entity_v1(data: &[u8]) -> Result<Vec<u8>> {
+    let mut fields = &data[8..];
+    let legacy = EntityV1::deserialize(&mut fields)?;
+    let entity = Entity {
+        version: 2,
+        entity_id: legacy.entity_id,
+        instance: legacy.instance,
+        registry: legacy.registry,
+        revision: 0,
+        component_revisions: legacy.components.keys().map(|key| (*key, 0)).collect(),
+        components: legacy.components,
+    };
+
+    let mut upgraded = Vec::new();
+    entity.try_serialize(&mut upgraded)?;
+    upgraded.resize(upgraded.len() + fields.len(), 0);
+    Ok(upgraded)
+}
+
+// Upgrades only ever grow an account
+pub fn write_layout<'info>(account: &AccountInfo<'info>, upgraded: &[u8], payer: &AccountInfo<'info>, system_program: &AccountInfo<'info>) -> Result<()> {
+    // Only top up rent, whatever the account holds above it isn't the payer's
+    if Rent::get()?.minimum_balance(upgraded.len()) > account.lamports() {
+        realloc_account(account, upgraded.len(), payer, system_program)?;
+    } else {
+        account.realloc(upgraded.len(), false)?;
+    }
+    account.try_borrow_mut_data()?.copy_from_slice(upgraded);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(components: Vec<(Pubkey, SerializedComponent)>) -> Entity {
+        Entity {
+            version: ENTITY_LAYOUT_VERSION,
+            entity_id: 0,
+            instance: 0,
+            registry: Pubkey::new_unique(),
+            revision: 0,
+            components: components.into_iter().collect(),
+            component_revisions: BTreeMap::new(),
+        }
+    }
+
+    fn component(max_size: u64, data: Vec<u8>) -> SerializedComponent {
+        SerializedComponent { max_size, data }
+    }
+
+    #[test]
+    fn removed_size_skips_unknown_and_repeated_components() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let entity = entity(vec![(a, component(8, vec![1])), (b, component(16, vec![2]))]);
+        let extra = SERIALIZED_COMPONENT_EXTRA_SPACE as usize;
+
+        assert_eq!(get_removed_size(&entity.components, &[a, b]), 8 + 16 + 2 * extra);
+        assert_eq!(get_removed_size(&entity.components, &[a, a]), 8 + extra);
+        assert_eq!(get_removed_size(&entity.components, &[Pubkey::new_unique()]), 0);
+    }
+
+    #[test]
+    fn added_components_fit_their_max_size() {
+        let a = Pubkey::new_unique();
+        let mut entity = entity(vec![]);
+
+        assert_eq!(add_entity_components(&mut entity, vec![(a, component(2, vec![1, 2, 3]))]).unwrap_err(), error!(ComponentError::InvalidDataLengthError));
+        assert!(entity.components.is_empty());
+        add_entity_components(&mut entity, vec![(a, component(3, vec![1, 2, 3]))]).unwrap();
+        assert_eq!(entity.components[&a].data, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn modified_components_exist_and_fit_their_max_size() {
+        let a = Pubkey::new_unique();
+        let mut entity = entity(vec![(a, component(2, vec![1]))]);
+
+        assert_eq!(modify_entity_components(&mut entity, vec![(Pubkey::new_unique(), vec![1])]).unwrap_err(), error!(ComponentError::ComponentNotFound));
+        assert_eq!(modify_entity_components(&mut entity, vec![(a, vec![1, 2, 3])]).unwrap_err(), error!(ComponentError::InvalidDataLengthError));
+        modify_entity_components(&mut entity, vec![(a, vec![4, 5])]).unwrap();
+        assert_eq!((entity.components[&a].max_size, entity.components[&a].data.clone()), (2, vec![4, 5]));
+    }
+
+    #[test]
+    fn overflow_references_only_change_through_overflow_instructions() {
+        let a = Pubkey::new_unique();
+        let reference = component(OVERFLOW_REFERENCE_MAX_SIZE, Pubkey::new_unique().to_bytes().to_vec());
+        let mut entity = entity(vec![(a, reference.clone())]);
+
+        assert_eq!(modify_entity_components(&mut entity, vec![(a, vec![1])]).unwrap_err(), error!(OverflowError::OverflowReference));
+        assert_eq!(add_entity_components(&mut entity, vec![(a, component(8, vec![1]))]).unwrap_err(), error!(OverflowError::OverflowReference));
+        assert_eq!(add_entity_components(&mut entity, vec![(Pubkey::new_unique(), reference)]).unwrap_err(), error!(OverflowError::OverflowReference));
+    }
+
+    #[test]
+    fn add_modify_and_remove_track_component_revisions() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut entity = entity(vec![]);
+
+        add_entity_components(&mut entity, vec![(a, component(4, vec![1])), (b, component(4, vec![2]))]).unwrap();
+        entity.bump_revision(&[a, b]);
+        assert_eq!(entity.revision, 1);
+        assert_eq!(entity.component_revisions, BTreeMap::from([(a, 1), (b, 1)]));
+
+        modify_entity_components(&mut entity, vec![(b, vec![3])]).unwrap();
+        entity.bump_revision(&[b]);
+        assert_eq!(entity.revision, 2);
+        assert_eq!(entity.component_revisions, BTreeMap::from([(a, 1), (b, 2)]));
+
+        // Removed components drop their revision and start over at the Entity's revision when added back
+        remove_entity_components(&mut entity, &[a]).unwrap();
+        entity.bump_revision(&[a]);
+        assert_eq!(entity.revision, 3);
+        assert_eq!(entity.component_revisions, BTreeMap::from([(b, 2)]));
+
+        add_entity_components(&mut entity, vec![(a, component(4, vec![4]))]).unwrap();
+        entity.bump_revision(&[a]);
+        assert_eq!(entity.revision, 4);
+        assert_eq!(entity.component_revisions, BTreeMap::from([(a, 4), (b, 2)]));
+    }
+
+    #[test]
+    fn removing_unknown_components_leaves_the_entity_untouched() {
+        let a = Pubkey::new_unique();
+        let mut entity = entity(vec![(a, component(4, vec![1]))]);
+        entity.bump_revision(&[a]);
+
+        assert_eq!(remove_entity_components(&mut entity, &[a, Pubkey::new_unique()]).unwrap_err(), error!(ComponentError::ComponentNotFound));
+        assert!(entity.components.contains_key(&a));
+        assert_eq!((entity.revision, entity.component_revisions.clone()), (1, BTreeMap::from([(a, 1)])));
+    }
+
+    #[test]
+    fn batched_operations_bump_the_revision_once() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mut entity = entity(vec![(a, component(4, vec![1])), (b, component(4, vec![2]))]);
+        entity.bump_revision(&[a, b]);
+
+        // Same path as mutate_components: b is removed and added back within the batch
+        let operations = vec![
+            ComponentOperation::Modify(a, vec![3]),
+            ComponentOperation::Remove(b),
+            ComponentOperation::Add(c, component(4, vec![4])),
+            ComponentOperation::Add(b, component(8, vec![5])),
+        ];
+        let changed: Vec<Pubkey> = operations.iter().map(|op| op.component()).collect();
+        entity.components = apply_component_operations(&entity.components, &operations).unwrap();
+        entity.bump_revision(&changed);
+        assert_eq!(entity.revision, 2);
+        assert_eq!(entity.component_revisions, BTreeMap::from([(a, 2), (b, 2), (c, 2)]));
+
+        let operations = vec![ComponentOperation::Remove(c)];
+        entity.components = apply_component_operations(&entity.components, &operations).unwrap();
+        entity.bump_revision(&[c]);
+        assert_eq!(entity.revision, 3);
+        assert_eq!(entity.component_revisions, BTreeMap::from([(a, 2), (b, 2)]));
+    }
+
+    #[test]
+    fn batch_entities_keep_their_own_revisions() {
+        let a = Pubkey::new_unique();
+        let mut entities = [entity(vec![]), entity(vec![(a, component(4, vec![1]))])];
+        entities[1].bump_revision(&[a]);
+
+        // Same path as batch_add_components, changes[i] applies to entities[i]
+        let changes = vec![vec![(a, component(4, vec![2]))], vec![(a, component(4, vec![3]))]];
+        for (entity, components) in entities.iter_mut().zip(changes) {
+            let changed: Vec<Pubkey> = components.iter().map(|tuple| tuple.0).collect();
+            add_entity_components(entity, components).unwrap();
+            entity.bump_revision(&changed);
+        }
+        assert_eq!((entities[0].revision, entities[0].component_revisions[&a]), (1, 1));
+        assert_eq!((entities[1].revision, entities[1].component_revisions[&a]), (2, 2));
+
+        for entity in entities.iter_mut() {
+            remove_entity_components(entity, &[a]).unwrap();
+            entity.bump_revision(&[a]);
+        }
+        assert!(entities.iter().all(|entity| entity.component_revisions.is_empty()));
+        assert_eq!((entities[0].revision, entities[1].revision), (2, 3));
+    }
+}
@@ -4,4 +4,100 @@ use anchor_lang::prelude::*;
 pub enum ComponentError {
     #[msg("Invalid Data Length!")]
     InvalidDataLengthError,
-}
\ No newline at end of file
+    #[msg("Component not found on Entity!")]
+    ComponentNotFound,
+}
+
+#[error_code(offset = 6100)]
+pub enum ARCNFTError {
+    #[msg("ARCNFT mints must have 0 decimals!")]
+    InvalidMintDecimals,
+    #[msg("ARCNFT mints can't have a supply greater than 1!")]
+    InvalidMintSupply,
+    #[msg("Mint authority must be revoked or held by the minter!")]
+    UncontrolledMintAuthority,
+    #[msg("Entity doesn't belong to the Registry Instance!")]
+    EntityInstanceMismatch,
+    #[msg("ARCNFT mint still has tokens in circulation!")]
+    MintNotBurned,
+    #[msg("Entity is still entangled with an ARCNFT!")]
+    EntityHasARCNFT,
+}
+
+#[error_code(offset = 6200)]
+pub enum BatchError {
+    #[msg("Number of changes doesn't match the number of Entities passed in!")]
+    EntityCountMismatch,
+    #[msg("Entity doesn't belong to the signing Registry!")]
+    RegistryMismatch,
+    #[msg("Entity must be writable!")]
+    EntityNotWritable,
+    #[msg("Entity is passed in more than once!")]
+    DuplicateEntity,
+}
+
+#[error_code(offset = 6300)]
+pub enum RelationError {
+    #[msg("Entity can't be related to itself!")]
+    SelfRelation,
+    #[msg("Related Entities must belong to the same Registry!")]
+    RegistryMismatch,
+    #[msg("Entity already has a parent!")]
+    AlreadyHasParent,
+    #[msg("Entity already references the target!")]
+    AlreadyAttached,
+    #[msg("Entity isn't attached to the target!")]
+    NotAttached,
+    #[msg("Entity still has relations!")]
+    RelationsNotEmpty,
+    #[msg("Entity relations have to be detached and closed first!")]
+    EntityHasRelations,
+}
+
+#[error_code(offset = 6400)]
+pub enum CloneError {
+    #[msg("Entities can only be cloned within the same Registry!")]
+    RegistryMismatch,
+}
+
+#[error_code(offset = 6500)]
+pub enum OverflowError {
+    #[msg("Entity already has this component!")]
+    ComponentExists,
+    #[msg("Overflow component is larger than an account can hold!")]
+    MaxSizeTooLarge,
+    #[msg("Write goes past the component's max size!")]
+    WriteOutOfBounds,
+    #[msg("Component isn't stored in this overflow account!")]
+    NotOverflowComponent,
+    #[msg("Overflow components can only be changed with the overflow instructions!")]
+    OverflowReference,
+}
+
+#[error_code(offset = 6600)]
+pub enum UploadError {
+    #[msg("Component is too large to commit to the Entity in one instruction!")]
+    MaxSizeTooLarge,
+    #[msg("Chunk goes past the component's max size!")]
+    WriteOutOfBounds,
+}
+
+#[error_code(offset = 6700)]
+pub enum CompressionError {
+    #[msg("Compressed Entity doesn't belong to the Registry Instance!")]
+    EntityInstanceMismatch,
+}
+
+#[error_code(offset = 6800)]
+pub enum MigrationError {
+    #[msg("Account type doesn't have a versioned layout!")]
+    UnknownAccount,
+    #[msg("Account data doesn't match any known layout of its type!")]
+    UnknownLayout,
+    #[msg("Account is already on the latest layout!")]
+    AlreadyCurrent,
+    #[msg("Account layout is newer than this program supports!")]
+    UnsupportedVersion,
+    #[msg("Account layout is outdated, migrate it first!")]
+    OutdatedLayout,
+}
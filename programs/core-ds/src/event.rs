@@ -33,3 +33,51 @@ pub struct ComponentModified{
     pub entity: Pubkey,
     pub components: Vec<Pubkey>
 }
+
+#[event]
+pub struct ComponentsMutated{
+    pub entity: Pubkey,
+    pub added: Vec<Pubkey>,
+    pub modified: Vec<Pubkey>,
+    pub removed: Vec<Pubkey>,
+}
+
+// One per Entity per mutating instruction, logged through the Noop program. Only hashes are logged, the data itself is in the instruction
+#[event]
+pub struct EntityMutation{
+    pub entity: Pubkey,
+    pub revision: u64, // Entity revision after the mutation, 0 when it was created
+    pub slot: u64,
+    pub action_bundle: Pubkey,
+    pub changes: Vec<ComponentChange>,
+}
+
+// Logged next to the EntityMutation of a write_component_data. The Entity only holds the ComponentData address, so the record's hashes don't change with the bytes
+#[event]
+pub struct ComponentDataWritten{
+    pub entity: Pubkey,
+    pub component: Pubkey,
+    pub revision: u64, // Entity revision the write was recorded at
+    pub offset: u64,
+    pub data: Vec<u8>,
+}
+
+// Logged through the Noop program (never truncated) so indexers can rebuild compressed Entities
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub enum CompressedEntityEvent {
+    Created {
+        merkle_tree: Pubkey,
+        index: u32,
+        entity: CompressedEntity,
+    },
+    Modified {
+        merkle_tree: Pubkey,
+        index: u32,
+        entity: CompressedEntity,
+    },
+    Removed {
+        merkle_tree: Pubkey,
+        index: u32,
+    },
+}
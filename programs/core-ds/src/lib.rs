@@ -1,3 +1,8 @@
+// Anchor's Error is large by design and every instruction returns it
+#![allow(clippy::result_large_err)]
+// Explicit returns are the house style
+#![allow(clippy::needless_return)]
+
 use anchor_lang::prelude::*;
 use std::collections::BTreeMap;
 
@@ -12,9 +17,9 @@ pub mod state;
 
 //use account::*;
 use context::*;
-//use constant::*;
-//use error::*;
-//use event::*;
+use constant::*;
+use error::*;
+use event::*;
 use state::*;
 
 #[program]
@@ -23,6 +28,7 @@ pub mod core_ds {
     use super::*;
 
     pub fn init_registry(ctx:Context<InitRegistryInstance>, registry:Pubkey, instance: u64) -> Result<()> {
+        ctx.accounts.registry_instance.version = LAYOUT_VERSION;
         ctx.accounts.registry_instance.registry = registry;
         ctx.accounts.registry_instance.instance = instance;
         
@@ -34,46 +40,482 @@ pub mod core_ds {
         ctx.accounts.registry_instance.entities += 1;
 
         // Set Entity Data
+        ctx.accounts.entity.version = ENTITY_LAYOUT_VERSION;
         ctx.accounts.entity.entity_id = entity_id;
         ctx.accounts.entity.registry = ctx.accounts.registry_instance.registry.key();
         ctx.accounts.entity.instance = ctx.accounts.registry_instance.instance;
+        ctx.accounts.entity.revision = 0;
+        for comp in components.values() {
+            check_inline(comp)?;
+            require!(comp.data.len() as u64 <= comp.max_size, ComponentError::InvalidDataLengthError);
+        }
+        ctx.accounts.entity.component_revisions = components.keys().map(|key| (*key, 0)).collect();
+        let old = components.keys().map(|key| (*key, None)).collect();
         ctx.accounts.entity.components = components;
+        record_mutation(ctx.accounts.entity.key(), &ctx.accounts.entity, old, ctx.accounts.action_bundle.key(), &ctx.accounts.noop_program)?;
         
         Ok(())
     }
     
+    pub fn clone_entity(ctx:Context<CloneEntity>, entity_id:u64, filter: Option<Vec<Pubkey>>) -> Result<()> {
+        if let Some(keys) = filter.as_ref() {
+            for key in keys {
+                require!(ctx.accounts.source.components.contains_key(key), ComponentError::ComponentNotFound);
+            }
+        }
+
+        // Increment registry Instance Entities
+        ctx.accounts.registry_instance.entities += 1;
+
+        // Set Entity Data
+        ctx.accounts.entity.version = ENTITY_LAYOUT_VERSION;
+        ctx.accounts.entity.entity_id = entity_id;
+        ctx.accounts.entity.registry = ctx.accounts.registry_instance.registry.key();
+        ctx.accounts.entity.instance = ctx.accounts.registry_instance.instance;
+        ctx.accounts.entity.revision = 0;
+        ctx.accounts.entity.components = filter_components(&ctx.accounts.source.components, &filter);
+        // The clone's ComponentData would live at a different address, so overflow components can't be copied over
+        for comp in ctx.accounts.entity.components.values() {
+            check_inline(comp)?;
+        }
+        ctx.accounts.entity.component_revisions = ctx.accounts.entity.components.keys().map(|key| (*key, 0)).collect();
+        let old = ctx.accounts.entity.components.keys().map(|key| (*key, None)).collect();
+        record_mutation(ctx.accounts.entity.key(), &ctx.accounts.entity, old, ctx.accounts.action_bundle.key(), &ctx.accounts.noop_program)?;
+
+        Ok(())
+    }
+    
     pub fn mint_arcnft(ctx:Context<MintARCNFT>) -> Result<()> {
+        ctx.accounts.arcnft.version = LAYOUT_VERSION;
         ctx.accounts.arcnft.entity = ctx.accounts.entity.key();
         ctx.accounts.arcnft.mint = ctx.accounts.mint.key();
+        ctx.accounts.entity_arcnft.version = LAYOUT_VERSION;
+        ctx.accounts.entity_arcnft.arcnft = ctx.accounts.arcnft.key();
+        ctx.accounts.entity_arcnft.mint = ctx.accounts.mint.key();
+        Ok(())
+    }
+
+    pub fn burn_arcnft(_ctx:Context<BurnARCNFT>) -> Result<()> {
         Ok(())
     }
 
     pub fn add_components(ctx:Context<AddComponent>, components:Vec<(Pubkey,SerializedComponent)>) -> Result<()> {
-        for comp in components {
-            ctx.accounts.entity.components.insert(comp.0, comp.1);
-        }
+        let changed: Vec<Pubkey> = components.iter().map(|tuple| tuple.0).collect();
+        let old = get_component_hashes(&ctx.accounts.entity, &changed);
+        add_entity_components(&mut ctx.accounts.entity, components)?;
+        ctx.accounts.entity.bump_revision(&changed);
+        record_mutation(ctx.accounts.entity.key(), &ctx.accounts.entity, old, ctx.accounts.action_bundle.key(), &ctx.accounts.noop_program)?;
         
         Ok(())
     }
 
     pub fn remove_component(ctx:Context<RemoveComponent>, removed_components: Vec<Pubkey>) -> Result<()> {
-        for comp in removed_components {
-            ctx.accounts.entity.components.remove(&comp);
-        }   
+        let old = get_component_hashes(&ctx.accounts.entity, &removed_components);
+        remove_entity_components(&mut ctx.accounts.entity, &removed_components)?;
+        ctx.accounts.entity.bump_revision(&removed_components);
+        record_mutation(ctx.accounts.entity.key(), &ctx.accounts.entity, old, ctx.accounts.action_bundle.key(), &ctx.accounts.noop_program)?;
 
         Ok(())
     }
 
     pub fn modify_components(ctx:Context<ModifyComponent>, components: Vec<(Pubkey, Vec<u8>)>) -> Result<()> {
-        for comp in components.iter() {
-            let mut new_comp = ctx.accounts.entity.components.get(&comp.0).unwrap().clone();
-            new_comp.data = comp.1.clone();
-            ctx.accounts.entity.components.insert(comp.0.clone(), new_comp);
+        let changed: Vec<Pubkey> = components.iter().map(|tuple| tuple.0).collect();
+        let old = get_component_hashes(&ctx.accounts.entity, &changed);
+        modify_entity_components(&mut ctx.accounts.entity, components)?;
+        ctx.accounts.entity.bump_revision(&changed);
+        record_mutation(ctx.accounts.entity.key(), &ctx.accounts.entity, old, ctx.accounts.action_bundle.key(), &ctx.accounts.noop_program)?;
+
+        Ok(())
+    }
+
+    /**
+     * Add, modify and remove in one go, the Entity is only realloc'd once for the net change
+     */
+    pub fn mutate_components(ctx:Context<MutateComponents>, operations: Vec<ComponentOperation>) -> Result<()> {
+        let changed: Vec<Pubkey> = operations.iter().map(|op| op.component()).collect();
+        let old = get_component_hashes(&ctx.accounts.entity, &changed);
+        let components = apply_component_operations(&ctx.accounts.entity.components, &operations)?;
+        let entity = ctx.accounts.entity.to_account_info();
+        let new_len = entity.data_len() + get_components_size(&components) - get_components_size(&ctx.accounts.entity.components);
+        realloc_account(&entity, new_len, &ctx.accounts.payer.to_account_info(), &ctx.accounts.system_program.to_account_info())?;
+        ctx.accounts.entity.components = components;
+        ctx.accounts.entity.bump_revision(&changed);
+        record_mutation(ctx.accounts.entity.key(), &ctx.accounts.entity, old, ctx.accounts.action_bundle.key(), &ctx.accounts.noop_program)?;
+
+        let mut diff = ComponentsMutated {
+            entity: entity.key(),
+            added: vec![],
+            modified: vec![],
+            removed: vec![],
+        };
+        for op in operations {
+            match op {
+                ComponentOperation::Add(component, _) => diff.added.push(component),
+                ComponentOperation::Modify(component, _) => diff.modified.push(component),
+                ComponentOperation::Remove(component) => diff.removed.push(component),
+            }
+        }
+        emit!(diff);
+
+        Ok(())
+    }
+
+    /**
+     * Components too large for the Entity keep their bytes in a ComponentData PDA, written in chunks
+     * The Entity only holds the address of that account
+     */
+    pub fn add_overflow_component(ctx:Context<AddOverflowComponent>, component: Pubkey, max_size: u64) -> Result<()> {
+        let old = get_component_hashes(&ctx.accounts.entity, &[component]);
+        ctx.accounts.component_data.header = ComponentBytesHeader::new(ctx.accounts.entity.key(), component, max_size);
+
+        ctx.accounts.entity.components.insert(component, SerializedComponent {
+            max_size: OVERFLOW_REFERENCE_MAX_SIZE,
+            data: ctx.accounts.component_data.key().to_bytes().to_vec()
+        });
+        ctx.accounts.entity.bump_revision(&[component]);
+        record_mutation(ctx.accounts.entity.key(), &ctx.accounts.entity, old, ctx.accounts.action_bundle.key(), &ctx.accounts.noop_program)?;
+        Ok(())
+    }
+
+    pub fn write_component_data(ctx:Context<WriteComponentData>, component: Pubkey, offset: u64, data: Vec<u8>) -> Result<()> {
+        let old = get_component_hashes(&ctx.accounts.entity, &[component]);
+        let reference = ctx.accounts.entity.components.get(&component).ok_or(ComponentError::ComponentNotFound)?;
+        require!(reference.is_overflow_reference() && reference.data == ctx.accounts.component_data.key().to_bytes().to_vec(), OverflowError::NotOverflowComponent);
+
+        // ComponentData grows as it's written
+        let end = offset.checked_add(data.len() as u64).ok_or(OverflowError::WriteOutOfBounds)?;
+        let component_data = ctx.accounts.component_data.to_account_info();
+        let new_len = COMPONENT_BYTES_OFFSET + end as usize;
+        if new_len > component_data.data_len() {
+            realloc_account(&component_data, new_len, &ctx.accounts.payer.to_account_info(), &ctx.accounts.system_program.to_account_info())?;
+        }
+        write_component_bytes(&component_data, &mut ctx.accounts.component_data.header, offset, &data, error!(OverflowError::WriteOutOfBounds))?;
+
+        ctx.accounts.entity.bump_revision(&[component]);
+        record_mutation(ctx.accounts.entity.key(), &ctx.accounts.entity, old, ctx.accounts.action_bundle.key(), &ctx.accounts.noop_program)?;
+        // The record's hashes cover the reference, which stays the same, so the written bytes are logged as well
+        record_component_data_write(ctx.accounts.entity.key(), &ctx.accounts.entity, component, offset, data, &ctx.accounts.noop_program)?;
+        Ok(())
+    }
+
+    pub fn remove_overflow_component(ctx:Context<RemoveOverflowComponent>, component: Pubkey) -> Result<()> {
+        let old = get_component_hashes(&ctx.accounts.entity, &[component]);
+        if let Some(reference) = ctx.accounts.entity.components.get(&component) {
+            require!(reference.is_overflow_reference() && reference.data == ctx.accounts.component_data.key().to_bytes().to_vec(), OverflowError::NotOverflowComponent);
+            ctx.accounts.entity.components.remove(&component);
+            ctx.accounts.entity.bump_revision(&[component]);
+            record_mutation(ctx.accounts.entity.key(), &ctx.accounts.entity, old, ctx.accounts.action_bundle.key(), &ctx.accounts.noop_program)?;
+        }
+        Ok(())
+    }
+
+    /**
+     * Staged upload for components too large to send in one instruction:
+     * allocate, write chunks across as many transactions as needed, then commit to the Entity
+     */
+    pub fn allocate_component(ctx:Context<AllocateComponent>, component: Pubkey, max_size: u64) -> Result<()> {
+        ctx.accounts.component_upload.header = ComponentBytesHeader::new(ctx.accounts.entity.key(), component, max_size);
+        Ok(())
+    }
+
+    pub fn write_component_chunk(ctx:Context<WriteComponentChunk>, _component: Pubkey, offset: u64, data: Vec<u8>) -> Result<()> {
+        // Allocated for the whole component up front
+        let component_upload = ctx.accounts.component_upload.to_account_info();
+        write_component_bytes(&component_upload, &mut ctx.accounts.component_upload.header, offset, &data, error!(UploadError::WriteOutOfBounds))
+    }
+
+    /**
+     * Adds the uploaded component to the Entity, or replaces it if the Entity already has it
+     */
+    pub fn commit_component(ctx:Context<CommitComponent>, component: Pubkey) -> Result<()> {
+        let old = get_component_hashes(&ctx.accounts.entity, &[component]);
+        let header = &ctx.accounts.component_upload.header;
+        let data = ctx.accounts.component_upload.to_account_info().try_borrow_data()?[header.get_bytes_range()].to_vec();
+        let serialized = SerializedComponent {
+            max_size: header.max_size,
+            data
+        };
+
+        let entity = ctx.accounts.entity.to_account_info();
+        let old_size = match ctx.accounts.entity.components.get(&component) {
+            Some(comp) => {
+                // Committing over an overflow reference would orphan its ComponentData account
+                check_inline(comp)?;
+                compute_comp_arr_max_size(&vec![comp.clone()])
+            },
+            None => 0,
+        };
+        let new_len = entity.data_len() + compute_comp_arr_max_size(&vec![serialized.clone()]) - old_size;
+        realloc_account(&entity, new_len, &ctx.accounts.payer.to_account_info(), &ctx.accounts.system_program.to_account_info())?;
+        ctx.accounts.entity.components.insert(component, serialized);
+        ctx.accounts.entity.bump_revision(&[component]);
+        record_mutation(ctx.accounts.entity.key(), &ctx.accounts.entity, old, ctx.accounts.action_bundle.key(), &ctx.accounts.noop_program)?;
+        Ok(())
+    }
+
+    pub fn cancel_component(_ctx:Context<CancelComponent>, _component: Pubkey) -> Result<()> {
+        Ok(())
+    }
+
+    /**
+     * Compressed Entities only keep a hash on chain, as a leaf in a concurrent Merkle tree owned by the Registry Instance.
+     * Full data is logged through the Noop program for indexers, and passed back in (with a proof) to change it.
+     */
+    pub fn init_compressed_tree(ctx:Context<InitCompressedTree>, max_depth: u32, max_buffer_size: u32) -> Result<()> {
+        ctx.accounts.compressed_tree.version = LAYOUT_VERSION;
+        ctx.accounts.compressed_tree.registry_instance = ctx.accounts.registry_instance.key();
+        ctx.accounts.compressed_tree.merkle_tree = ctx.accounts.merkle_tree.key();
+        ctx.accounts.compressed_tree.leaves = 0;
+
+        let registry = ctx.accounts.registry_instance.registry.key();
+        let instance = ctx.accounts.registry_instance.instance.to_be_bytes();
+        let instance_signer_seeds:&[&[u8]] = &[
+            SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry.as_ref(),
+            instance.as_ref(),
+            &[*ctx.bumps.get("registry_instance").unwrap()]
+        ];
+        let signer_seeds = &[instance_signer_seeds];
+
+        spl_account_compression::cpi::init_empty_merkle_tree(CpiContext::new_with_signer(
+            ctx.accounts.compression_program.to_account_info(),
+            spl_account_compression::cpi::accounts::Initialize {
+                merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+                authority: ctx.accounts.registry_instance.to_account_info(),
+                noop: ctx.accounts.noop_program.to_account_info(),
+            },
+            signer_seeds
+        ), max_depth, max_buffer_size)?;
+
+        Ok(())
+    }
+
+    /**
+     * Compressed Entities are addressed by their tree and leaf index. entity_id is only stored in the leaf,
+     * nothing on chain keeps track of it, so it isn't checked for uniqueness like a regular Entity's PDA is.
+     */
+    pub fn init_compressed_entity(ctx:Context<CompressedEntities>, entity_id: u64, components: BTreeMap<Pubkey, SerializedComponent>) -> Result<()> {
+        for comp in components.values() {
+            check_inline(comp)?;
+            require!(comp.data.len() as u64 <= comp.max_size, ComponentError::InvalidDataLengthError);
+        }
+        let entity = CompressedEntity {
+            entity_id,
+            instance: ctx.accounts.registry_instance.instance,
+            registry: ctx.accounts.registry_instance.registry.key(),
+            components,
+        };
+        let index = ctx.accounts.compressed_tree.leaves as u32;
+        ctx.accounts.compressed_tree.leaves += 1;
+
+        let registry = ctx.accounts.registry_instance.registry.key();
+        let instance = ctx.accounts.registry_instance.instance.to_be_bytes();
+        let instance_signer_seeds:&[&[u8]] = &[
+            SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry.as_ref(),
+            instance.as_ref(),
+            &[*ctx.bumps.get("registry_instance").unwrap()]
+        ];
+        let signer_seeds = &[instance_signer_seeds];
+
+        spl_account_compression::cpi::append(CpiContext::new_with_signer(
+            ctx.accounts.compression_program.to_account_info(),
+            spl_account_compression::cpi::accounts::Modify {
+                merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+                authority: ctx.accounts.registry_instance.to_account_info(),
+                noop: ctx.accounts.noop_program.to_account_info(),
+            },
+            signer_seeds
+        ), entity.hash()?)?;
+
+        let event = CompressedEntityEvent::Created {
+            merkle_tree: ctx.accounts.merkle_tree.key(),
+            index,
+            entity
+        };
+        spl_account_compression::wrap_application_data_v1(event.try_to_vec()?, &ctx.accounts.noop_program)?;
+        Ok(())
+    }
+
+    pub fn modify_compressed_entity<'info>(ctx:Context<'_, '_, '_, 'info, CompressedEntities<'info>>, root: [u8; 32], entity: CompressedEntity, index: u32, operations: Vec<ComponentOperation>) -> Result<()> {
+        require!(entity.registry == ctx.accounts.registry_instance.registry && entity.instance == ctx.accounts.registry_instance.instance, CompressionError::EntityInstanceMismatch);
+        let previous_leaf = entity.hash()?;
+        let mut entity = entity;
+        entity.components = apply_component_operations(&entity.components, &operations)?;
+
+        let registry = ctx.accounts.registry_instance.registry.key();
+        let instance = ctx.accounts.registry_instance.instance.to_be_bytes();
+        let instance_signer_seeds:&[&[u8]] = &[
+            SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry.as_ref(),
+            instance.as_ref(),
+            &[*ctx.bumps.get("registry_instance").unwrap()]
+        ];
+        let signer_seeds = &[instance_signer_seeds];
+
+        // Fails unless the previous entity, index and proof match the tree
+        spl_account_compression::cpi::replace_leaf(CpiContext::new_with_signer(
+            ctx.accounts.compression_program.to_account_info(),
+            spl_account_compression::cpi::accounts::Modify {
+                merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+                authority: ctx.accounts.registry_instance.to_account_info(),
+                noop: ctx.accounts.noop_program.to_account_info(),
+            },
+            signer_seeds
+        ).with_remaining_accounts(ctx.remaining_accounts.to_vec()), root, previous_leaf, entity.hash()?, index)?;
+
+        let event = CompressedEntityEvent::Modified {
+            merkle_tree: ctx.accounts.merkle_tree.key(),
+            index,
+            entity
+        };
+        spl_account_compression::wrap_application_data_v1(event.try_to_vec()?, &ctx.accounts.noop_program)?;
+        Ok(())
+    }
+
+    pub fn remove_compressed_entity<'info>(ctx:Context<'_, '_, '_, 'info, CompressedEntities<'info>>, root: [u8; 32], entity: CompressedEntity, index: u32) -> Result<()> {
+        require!(entity.registry == ctx.accounts.registry_instance.registry && entity.instance == ctx.accounts.registry_instance.instance, CompressionError::EntityInstanceMismatch);
+
+        let registry = ctx.accounts.registry_instance.registry.key();
+        let instance = ctx.accounts.registry_instance.instance.to_be_bytes();
+        let instance_signer_seeds:&[&[u8]] = &[
+            SEEDS_REGISTRYINSTANCE_PREFIX,
+            registry.as_ref(),
+            instance.as_ref(),
+            &[*ctx.bumps.get("registry_instance").unwrap()]
+        ];
+        let signer_seeds = &[instance_signer_seeds];
+
+        // Removed leaves are zeroed, same as an empty leaf
+        spl_account_compression::cpi::replace_leaf(CpiContext::new_with_signer(
+            ctx.accounts.compression_program.to_account_info(),
+            spl_account_compression::cpi::accounts::Modify {
+                merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+                authority: ctx.accounts.registry_instance.to_account_info(),
+                noop: ctx.accounts.noop_program.to_account_info(),
+            },
+            signer_seeds
+        ).with_remaining_accounts(ctx.remaining_accounts.to_vec()), root, entity.hash()?, [0; 32], index)?;
+
+        let event = CompressedEntityEvent::Removed {
+            merkle_tree: ctx.accounts.merkle_tree.key(),
+            index
+        };
+        spl_account_compression::wrap_application_data_v1(event.try_to_vec()?, &ctx.accounts.noop_program)?;
+        Ok(())
+    }
+
+    /**
+     * Upgrades any CoreDS account to the latest layout in place. Only the layout changes so anyone can pay for it.
+     */
+    pub fn migrate_account(ctx:Context<MigrateAccount>) -> Result<()> {
+        let account = ctx.accounts.account.to_account_info();
+        let upgraded = {
+            let data = account.try_borrow_data()?;
+            let version = get_layout_version(account.key, &data)?;
+            require!(version < get_latest_layout_version(&data[..8]), MigrationError::AlreadyCurrent);
+            upgrade_layout(account.key, &data)?
+        };
+        write_layout(&account, &upgraded, &ctx.accounts.payer.to_account_info(), &ctx.accounts.system_program.to_account_info())
+    }
+
+    /**
+     * Batch variants take the Entities as remaining_accounts, changes[i] applies to remaining_accounts[i]
+     */
+    pub fn batch_add_components<'info>(ctx:Context<'_, '_, '_, 'info, BatchEntities<'info>>, changes: Vec<Vec<(Pubkey,SerializedComponent)>>) -> Result<()> {
+        let entities = load_batch_entities(ctx.remaining_accounts, &ctx.accounts.registry_signer, changes.len())?;
+        for (mut entity, components) in entities.into_iter().zip(changes) {
+            let new_len = entity.to_account_info().data_len() + compute_comp_arr_max_size(&components.iter().map(|tuple| tuple.1.clone() ).collect());
+            realloc_account(&entity.to_account_info(), new_len, &ctx.accounts.payer.to_account_info(), &ctx.accounts.system_program.to_account_info())?;
+            let changed: Vec<Pubkey> = components.iter().map(|tuple| tuple.0).collect();
+            let old = get_component_hashes(&entity, &changed);
+            add_entity_components(&mut entity, components)?;
+            entity.bump_revision(&changed);
+            record_mutation(entity.key(), &entity, old, ctx.accounts.action_bundle.key(), &ctx.accounts.noop_program)?;
+            entity.exit(ctx.program_id)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn batch_remove_components<'info>(ctx:Context<'_, '_, '_, 'info, BatchEntities<'info>>, changes: Vec<Vec<Pubkey>>) -> Result<()> {
+        let entities = load_batch_entities(ctx.remaining_accounts, &ctx.accounts.registry_signer, changes.len())?;
+        for (mut entity, removed_components) in entities.into_iter().zip(changes) {
+            let new_len = entity.to_account_info().data_len() - get_removed_size(&entity.components, &removed_components);
+            let old = get_component_hashes(&entity, &removed_components);
+            remove_entity_components(&mut entity, &removed_components)?;
+            entity.bump_revision(&removed_components);
+            record_mutation(entity.key(), &entity, old, ctx.accounts.action_bundle.key(), &ctx.accounts.noop_program)?;
+            realloc_account(&entity.to_account_info(), new_len, &ctx.accounts.payer.to_account_info(), &ctx.accounts.system_program.to_account_info())?;
+            entity.exit(ctx.program_id)?;
         }
 
         Ok(())
     }
 
+    pub fn batch_modify_components<'info>(ctx:Context<'_, '_, '_, 'info, BatchEntities<'info>>, changes: Vec<Vec<(Pubkey, Vec<u8>)>>) -> Result<()> {
+        let entities = load_batch_entities(ctx.remaining_accounts, &ctx.accounts.registry_signer, changes.len())?;
+        for (mut entity, components) in entities.into_iter().zip(changes) {
+            let changed: Vec<Pubkey> = components.iter().map(|tuple| tuple.0).collect();
+            let old = get_component_hashes(&entity, &changed);
+            modify_entity_components(&mut entity, components)?;
+            entity.bump_revision(&changed);
+            record_mutation(entity.key(), &entity, old, ctx.accounts.action_bundle.key(), &ctx.accounts.noop_program)?;
+            entity.exit(ctx.program_id)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn init_relations(ctx:Context<InitRelations>) -> Result<()> {
+        ctx.accounts.entity_relations.version = LAYOUT_VERSION;
+        ctx.accounts.entity_relations.entity = ctx.accounts.entity.key();
+        Ok(())
+    }
+
+    /**
+     * Both Entities need their relations initalized first
+     */
+    pub fn attach(ctx:Context<Attach>, relation: Relation) -> Result<()> {
+        let entity = ctx.accounts.entity.key();
+        let target = ctx.accounts.target.key();
+        match relation {
+            Relation::Child => {
+                require!(ctx.accounts.entity_relations.parent.is_none(), RelationError::AlreadyHasParent);
+                ctx.accounts.entity_relations.parent = Some(target);
+                ctx.accounts.target_relations.children.insert(entity);
+            },
+            Relation::Reference(kind) => {
+                require!(!ctx.accounts.entity_relations.references.contains_key(&target), RelationError::AlreadyAttached);
+                ctx.accounts.entity_relations.references.insert(target, kind);
+                ctx.accounts.target_relations.referenced_by += 1;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn detach(ctx:Context<Detach>, relation: Relation) -> Result<()> {
+        let entity = ctx.accounts.entity.key();
+        let target = ctx.accounts.target.key();
+        match relation {
+            Relation::Child => {
+                require!(ctx.accounts.entity_relations.parent == Some(target), RelationError::NotAttached);
+                ctx.accounts.entity_relations.parent = None;
+                ctx.accounts.target_relations.children.remove(&entity);
+            },
+            Relation::Reference(kind) => {
+                require!(ctx.accounts.entity_relations.references.get(&target) == Some(&kind), RelationError::NotAttached);
+                ctx.accounts.entity_relations.references.remove(&target);
+                ctx.accounts.target_relations.referenced_by -= 1;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn close_relations(_ctx:Context<CloseRelations>) -> Result<()> {
+        Ok(())
+    }
+
     pub fn remove_entity(_ctx:Context<RemoveEntity>) -> Result<()> {
         Ok(())
     }
@@ -1,6 +1,9 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+use std::collections::BTreeMap;
 
 use crate::account::MaxSize;
+use crate::constant::{COMPONENT_BYTES_OFFSET, LAYOUT_VERSION, OVERFLOW_REFERENCE_MAX_SIZE};
 
 #[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
 #[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
@@ -13,4 +16,142 @@ impl MaxSize for SerializedComponent {
     fn get_max_size() -> u64 {
         return 8+4;
     }
-}
\ No newline at end of file
+}
+
+impl SerializedComponent {
+    // Overflow components hold the address of their ComponentData account instead of their data
+    pub fn is_overflow_reference(&self) -> bool {
+        return self.max_size == OVERFLOW_REFERENCE_MAX_SIZE;
+    }
+
+    // Bytes the component's data takes up on the Entity
+    pub fn get_space(&self) -> usize {
+        if self.is_overflow_reference() {
+            return 32;
+        }
+        return self.max_size as usize;
+    }
+}
+
+// Header of the accounts that hold a component's raw bytes (ComponentData and ComponentUpload), the bytes follow it
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct ComponentBytesHeader {
+    pub version: u8,
+    pub entity: Pubkey,
+    pub component: Pubkey,
+    pub max_size: u64,
+    pub len: u64, // Highest byte written so far
+}
+
+impl MaxSize for ComponentBytesHeader {
+    fn get_max_size() -> u64 {
+        return 1 + 32 + 32 + 8 + 8;
+    }
+}
+
+impl ComponentBytesHeader {
+    pub fn new(entity: Pubkey, component: Pubkey, max_size: u64) -> Self {
+        return ComponentBytesHeader {
+            version: LAYOUT_VERSION,
+            entity,
+            component,
+            max_size,
+            len: 0,
+        };
+    }
+
+    // Where the written bytes sit in the account's data
+    pub fn get_bytes_range(&self) -> std::ops::Range<usize> {
+        return COMPONENT_BYTES_OFFSET..COMPONENT_BYTES_OFFSET + self.len as usize;
+    }
+}
+
+// A single step in a mutate_components call, applied in order
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub enum ComponentOperation {
+    Add(Pubkey, SerializedComponent),
+    Modify(Pubkey, Vec<u8>),
+    Remove(Pubkey),
+}
+
+impl ComponentOperation {
+    pub fn component(&self) -> Pubkey {
+        match self {
+            ComponentOperation::Add(component, _) => *component,
+            ComponentOperation::Modify(component, _) => *component,
+            ComponentOperation::Remove(component) => *component,
+        }
+    }
+}
+
+// A component's max size and the keccak hash of its data, as logged in an Entity's history
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ComponentHash {
+    pub max_size: u64,
+    pub hash: [u8; 32],
+}
+
+impl ComponentHash {
+    pub fn new(component: &SerializedComponent) -> Self {
+        return ComponentHash {
+            max_size: component.max_size,
+            hash: keccak::hash(&component.data).to_bytes(),
+        };
+    }
+}
+
+// None on either side when the component didn't exist before or doesn't after
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct ComponentChange {
+    pub component: Pubkey,
+    pub old: Option<ComponentHash>,
+    pub new: Option<ComponentHash>,
+}
+
+// How an Entity links to a target Entity
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    // Entity is a child of the target
+    Child,
+    // Entity references the target, what the kind means is up to the Registry
+    Reference(u16),
+}
+
+impl Relation {
+    // Space the Entity's relations need for the link
+    pub fn entity_size(&self) -> usize {
+        match self {
+            Relation::Child => 0, // parent is always allocated
+            Relation::Reference(_) => 32 + 2,
+        }
+    }
+
+    // Space the target's relations need for the link
+    pub fn target_size(&self) -> usize {
+        match self {
+            Relation::Child => 32,
+            Relation::Reference(_) => 0, // only counted
+        }
+    }
+}
+
+// Everything a compressed Entity holds, only its hash is kept on chain as a leaf of the Instance's Merkle tree
+#[cfg_attr(feature = "sdk", derive(serde::Serialize, serde::Deserialize))]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct CompressedEntity {
+    pub entity_id: u64,
+    pub instance: u64,
+    pub registry: Pubkey,
+    pub components: BTreeMap<Pubkey, SerializedComponent>,
+}
+
+impl CompressedEntity {
+    pub fn hash(&self) -> Result<[u8; 32]> {
+        return Ok(keccak::hashv(&[&self.try_to_vec()?]).to_bytes());
+    }
+}
@@ -1,48 +1,53 @@
 use anchor_lang::prelude::*;
 use core_ds::account::MaxSize;
-use std::collections::BTreeSet;
+use core_ds::state::SerializedComponent;
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::constant::STRING_MAX_SIZE;
 
-//use crate::state::*;
+use crate::state::*;
 
 #[account]
 pub struct RegistryConfig{
+    pub version: u8,
     pub core_ds: Pubkey,
     pub components: u64,
 }
 
 impl MaxSize for RegistryConfig {
     fn get_max_size() -> u64 {
-        return 32+8;
+        return 1+32+8;
     }
 }
 
 #[account]
 pub struct InstanceAuthority{
+    pub version: u8,
     pub instance: u64,
     pub authority: Pubkey
 }
 
 impl MaxSize for InstanceAuthority {
     fn get_max_size() -> u64 {
-        return 8+32;
+        return 1+8+32;
     }
 }
 
 #[account]
 pub struct ComponentSchema{
+    pub version: u8,
     pub url: String,
 }
 
 impl MaxSize for ComponentSchema {
     fn get_max_size() -> u64 {
-        return STRING_MAX_SIZE;
+        return 1+STRING_MAX_SIZE;
     }
 }
 
 #[account]
 pub struct ActionBundleRegistration{
+    pub version: u8,
     pub action_bundle: Pubkey,
     pub instances: BTreeSet<u64>,
     pub can_mint: bool,
@@ -51,6 +56,37 @@ pub struct ActionBundleRegistration{
 
 impl MaxSize for ActionBundleRegistration {
     fn get_max_size() -> u64 {
-        return 32+8+1+4;
+        return 1+32+8+1+4;
     }
-}
\ No newline at end of file
+}
+
+// Published by an Action Bundle to declare everything it needs from a Registry
+#[account]
+pub struct ActionBundleManifest{
+    pub version: u8,
+    pub action_bundle: Pubkey,
+    pub can_mint: bool,
+    pub instances: BTreeSet<u64>,
+    pub components: BTreeMap<Pubkey, ComponentAccess>, //PDA of the Component Schema
+}
+
+impl MaxSize for ActionBundleManifest {
+    fn get_max_size() -> u64 {
+        return 1+32+1+4+4;
+    }
+}
+
+// Entity shape that bundles can instantiate with init_entity_from_prefab
+#[account]
+pub struct Prefab{
+    pub version: u8,
+    pub authority: Pubkey,
+    pub name: String,
+    pub components: BTreeMap<Pubkey, SerializedComponent>, //Default data and max size per Component Schema
+}
+
+impl MaxSize for Prefab {
+    fn get_max_size() -> u64 {
+        return 1+32+4+4;
+    }
+}
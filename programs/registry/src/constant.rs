@@ -1 +1,5 @@
-pub const STRING_MAX_SIZE:u64 = 256;
\ No newline at end of file
+pub const STRING_MAX_SIZE:u64 = 256;
+// Prefab names are used as a seed
+pub const PREFAB_NAME_MAX_SIZE:usize = 32;
+// Layout version written to every account, accounts created before versioning read as 0
+pub const LAYOUT_VERSION:u8 = 1;
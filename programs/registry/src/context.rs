@@ -1,16 +1,23 @@
 use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use anchor_lang::solana_program::hash::hash;
 use anchor_spl::token::Mint;
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
 
 use crate::account::*;
 use crate::constant::*;
+use crate::error::*;
+use crate::state::*;
 
 use core_ds::{
     self,
     account::*,
+    constant::{ENTITY_LAYOUT_VERSION, LAYOUT_VERSION as CORE_LAYOUT_VERSION},
+    context::{filter_components, get_fixed_layout_version, get_pda_layout_version, read_pubkey},
+    error::MigrationError,
     program::CoreDs,
-    state::SerializedComponent
+    state::{SerializedComponent, ComponentOperation}
 };
 
 #[derive(Accounts)]
@@ -39,6 +46,7 @@ pub struct InstanceRegistry<'info>{
         mut,
         seeds=[b"registry_signer"],
         bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub registry_config: Account<'info, RegistryConfig>,
 
@@ -76,7 +84,7 @@ pub struct RegisterComponent<'info>{
             schema.as_bytes(),
         ],
         bump,
-        space=8+(STRING_MAX_SIZE as usize)
+        space=8+ComponentSchema::get_max_size() as usize
     )]
     pub component: Account<'info, ComponentSchema>,
 
@@ -84,6 +92,7 @@ pub struct RegisterComponent<'info>{
         mut,
         seeds=[b"registry_signer"],
         bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub registry_config: Account<'info, RegistryConfig>,
 }
@@ -97,15 +106,19 @@ pub struct RegisterSystem <'info> {
     /// CoreDS Registry Instance Account
     /// Make sure that its a Registry instance that belongs to *this* Registry
     #[account(
-        constraint = registry_instance.registry.key() == program_id.key()
+        constraint = registry_instance.registry.key() == program_id.key(),
+        constraint = registry_instance.version == CORE_LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub registry_instance: Account<'info, RegistryInstance>,
 
-    /// Make sure the instance authority is of the registry instance that's passed in
+    /// Make sure the instance authority is of the registry instance that's passed in, and that it approves the bundle
     #[account(
-        constraint = instance_authority.instance == registry_instance.instance
+        constraint = instance_authority.instance == registry_instance.instance,
+        constraint = instance_authority.authority == authority.key() @ RegistryError::InvalidInstanceAuthority,
+        constraint = instance_authority.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub instance_authority: Account<'info, InstanceAuthority>,
+    pub authority: Signer<'info>,
     
     #[account(
         init,
@@ -135,15 +148,19 @@ pub struct AddComponentsToActionBundleRegistration <'info> {
     /// CoreDS Registry Instance Account
     /// Make sure that its a Registry instance that belongs to *this* Registry
     #[account(
-        constraint = registry_instance.registry.key() == program_id.key()
+        constraint = registry_instance.registry.key() == program_id.key(),
+        constraint = registry_instance.version == CORE_LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub registry_instance: Account<'info, RegistryInstance>,
 
-    /// Make sure the instance authority is of the Registry instance that's passed in
+    /// Make sure the instance authority is of the Registry instance that's passed in, and that it approves the components
     #[account(
-        constraint = instance_authority.instance == registry_instance.instance
+        constraint = instance_authority.instance == registry_instance.instance,
+        constraint = instance_authority.authority == authority.key() @ RegistryError::InvalidInstanceAuthority,
+        constraint = instance_authority.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub instance_authority: Account<'info, InstanceAuthority>,
+    pub authority: Signer<'info>,
     
     #[account(
         mut,
@@ -156,6 +173,7 @@ pub struct AddComponentsToActionBundleRegistration <'info> {
             action_bundle.key().as_ref()
         ],
         bump,
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
 
@@ -174,13 +192,15 @@ pub struct AddInstancesToActionBundleRegistration <'info> {
     /// CoreDS Registry Instance Account
     /// Make sure that its a Registry instance that belongs to *this* Registry
     #[account(
-        constraint = registry_instance.registry.key() == program_id.key()
+        constraint = registry_instance.registry.key() == program_id.key(),
+        constraint = registry_instance.version == CORE_LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub registry_instance: Account<'info, RegistryInstance>,
 
     /// Make sure the instance authority is of the Registry instance that's passed in
     #[account(
-        constraint = instance_authority.instance == registry_instance.instance
+        constraint = instance_authority.instance == registry_instance.instance,
+        constraint = instance_authority.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub instance_authority: Account<'info, InstanceAuthority>,
     
@@ -195,6 +215,83 @@ pub struct AddInstancesToActionBundleRegistration <'info> {
             action_bundle.key().as_ref()
         ],
         bump,
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
+
+    /// CHECK: This can be any pubkey, but likely will be pubkey of 
+    /// PDA Signer from System
+    pub action_bundle: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(components: Vec<(Pubkey, ComponentAccess)>, can_mint: bool, instances: Vec<u64>)]
+pub struct PublishManifest<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        init,
+        payer=payer,
+        seeds=[
+            b"action_bundle_manifest",
+            action_bundle.key().as_ref()
+        ],
+        bump,
+        space=8+ActionBundleManifest::get_max_size() as usize+(instances.len()*8)+(components.len()*(32+1))
+    )]
+    pub action_bundle_manifest: Account<'info, ActionBundleManifest>,
+
+    // Only the Action Bundle can speak for itself
+    pub action_bundle: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterFromManifest<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// CoreDS Registry Instance Account
+    /// Make sure that its a Registry instance that belongs to *this* Registry
+    #[account(
+        constraint = registry_instance.registry.key() == program_id.key(),
+        constraint = registry_instance.version == CORE_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_instance: Account<'info, RegistryInstance>,
+
+    /// Make sure the instance authority is of the registry instance that's passed in, and that it approves the bundle
+    #[account(
+        constraint = instance_authority.instance == registry_instance.instance,
+        constraint = instance_authority.authority == authority.key() @ RegistryError::InvalidInstanceAuthority,
+        constraint = instance_authority.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub instance_authority: Account<'info, InstanceAuthority>,
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds=[
+            b"action_bundle_manifest",
+            action_bundle.key().as_ref()
+        ],
+        bump,
+        constraint = action_bundle_manifest.instances.contains(&registry_instance.instance) @ RegistryError::InstanceNotInManifest,
+        constraint = action_bundle_manifest.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub action_bundle_manifest: Account<'info, ActionBundleManifest>,
+
+    // Sized for the manifest's components and the one instance being approved
+    #[account(
+        init,
+        payer=payer,
+        seeds=[
+            b"action_bundle_registration",
+            registry_instance.key().as_ref(),
+            action_bundle.key().as_ref()
+        ],
+        bump,
+        space=8+ActionBundleRegistration::get_max_size() as usize+8+(get_write_components(&action_bundle_manifest).len()*32)
     )]
     pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
 
@@ -214,6 +311,7 @@ pub struct InitEntity<'info> {
     #[account(
         seeds=[b"registry_signer"],
         bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub registry_config: Account<'info, RegistryConfig>,
     
@@ -222,16 +320,153 @@ pub struct InitEntity<'info> {
     pub entity: AccountInfo<'info>,
     
     #[account(
-        constraint = registry_instance.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&registry_instance.instance)
+        mut,
+        constraint = registry_instance.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&registry_instance.instance),
+        constraint = registry_instance.version == CORE_LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub registry_instance: Account<'info, RegistryInstance>,
     pub action_bundle: Signer<'info>,
     // All action_bundles can make any entities they want
     #[account(
-        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key() && check_sys_registry(&components.keys().cloned().collect(), &action_bundle_registration.components)
+        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key() && check_sys_registry(&components.keys().cloned().collect(), &action_bundle_registration.components),
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
-    pub core_ds: Program<'info, CoreDs>,     
+    pub core_ds: Program<'info, CoreDs>,
+
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String, components: BTreeMap<Pubkey, SerializedComponent>)]
+pub struct CreatePrefab<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    #[account(
+        init,
+        payer=payer,
+        // Hashed so names of any length make a valid seed, and the length check below gets to run
+        seeds=[
+            b"prefab",
+            authority.key().as_ref(),
+            hash(name.as_bytes()).as_ref()
+        ],
+        bump,
+        constraint = name.len() <= PREFAB_NAME_MAX_SIZE @ RegistryError::PrefabNameTooLong,
+        space=8+Prefab::get_max_size() as usize+name.len()+get_prefab_components_size(&components)
+    )]
+    pub prefab: Account<'info, Prefab>,
+
+    // Prefabs are namespaced by whoever creates them
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePrefab<'info> {
+    #[account(mut)]
+    pub benefactor: Signer<'info>,
+
+    #[account(
+        mut,
+        close=benefactor,
+        has_one=authority,
+        constraint = prefab.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub prefab: Account<'info, Prefab>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(entity_id:u64, overrides: Vec<(Pubkey, Vec<u8>)>)]
+pub struct InitEntityFromPrefab<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Used to Sign Tx for the CPI
+    #[account(
+        seeds=[b"registry_signer"],
+        bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    
+    /// CHECK: Initalized via CPI
+    #[account(mut)]
+    pub entity: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        constraint = registry_instance.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&registry_instance.instance),
+        constraint = registry_instance.version == CORE_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_instance: Account<'info, RegistryInstance>,
+
+    #[account(
+        constraint = prefab.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub prefab: Account<'info, Prefab>,
+
+    pub action_bundle: Signer<'info>,
+    // The bundle needs permissions for every component in the prefab, not just the overridden ones
+    #[account(
+        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key() && check_sys_registry(&prefab.components.keys().cloned().collect(), &action_bundle_registration.components),
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
+    pub core_ds: Program<'info, CoreDs>,
+
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(entity_id:u64, filter: Option<Vec<Pubkey>>)]
+pub struct CloneEntity<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Used to Sign Tx for the CPI
+    #[account(
+        seeds=[b"registry_signer"],
+        bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    #[account(
+        constraint = source.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&source.instance),
+        constraint = source.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub source: Box<Account<'info, Entity>>,
+    
+    /// CHECK: Initalized via CPI
+    #[account(mut)]
+    pub entity: AccountInfo<'info>,
+    
+    // Bundle has to be registered on both the source and the destination instance
+    #[account(
+        mut,
+        constraint = registry_instance.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&registry_instance.instance),
+        constraint = registry_instance.version == CORE_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_instance: Account<'info, RegistryInstance>,
+    pub action_bundle: Signer<'info>,
+    // Bundle needs permissions for every component that gets copied
+    #[account(
+        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key() && check_sys_registry(&filter_components(&source.components, &filter).keys().cloned().collect(), &action_bundle_registration.components),
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
+    pub core_ds: Program<'info, CoreDs>,
+
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
@@ -244,17 +479,26 @@ pub struct MintARCNFT<'info> {
     #[account(
         seeds=[b"registry_signer"],
         bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub registry_config: Account<'info, RegistryConfig>,
     
+    #[account(
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
     pub entity: Box<Account<'info, Entity>>,
     pub mint: Account<'info, Mint>,
     
     /// CHECK: Created in CoreDS
+    #[account(mut)]
     pub arcnft: AccountInfo<'info>,
+    /// CHECK: Created in CoreDS
+    #[account(mut)]
+    pub entity_arcnft: AccountInfo<'info>,
 
     #[account(
-        constraint = registry_instance.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&registry_instance.instance)
+        constraint = registry_instance.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&registry_instance.instance),
+        constraint = registry_instance.version == CORE_LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub registry_instance: Account<'info, RegistryInstance>,
 
@@ -264,12 +508,52 @@ pub struct MintARCNFT<'info> {
     pub action_bundle: Signer<'info>,
 
     #[account(
-        constraint = action_bundle_registration.can_mint == true
+        constraint = action_bundle_registration.can_mint,
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
     pub core_ds: Program<'info, CoreDs>,     
 }
 
+#[derive(Accounts)]
+pub struct BurnARCNFT<'info> {
+    #[account(mut)]
+    pub benefactor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Used to Sign Tx for the CPI
+    #[account(
+        seeds=[b"registry_signer"],
+        bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    #[account(
+        constraint = entity.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&entity.instance),
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+    pub mint: Account<'info, Mint>,
+
+    /// CHECK: Closed in CoreDS
+    #[account(mut)]
+    pub arcnft: AccountInfo<'info>,
+    /// CHECK: Closed in CoreDS
+    #[account(mut)]
+    pub entity_arcnft: AccountInfo<'info>,
+
+    pub action_bundle: Signer<'info>,
+
+    // Only action bundles that can mint can burn
+    #[account(
+        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key() && action_bundle_registration.can_mint,
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
+    pub core_ds: Program<'info, CoreDs>,
+}
+
 #[derive(Accounts)]
 #[instruction(components: Vec<(Pubkey, SerializedComponent)>)]
 pub struct AddComponents<'info>{
@@ -281,12 +565,14 @@ pub struct AddComponents<'info>{
     #[account(
         seeds=[b"registry_signer"],
         bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub registry_config: Account<'info, RegistryConfig>,
 
     #[account(
         mut,
-        constraint = entity.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&entity.instance)
+        constraint = entity.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&entity.instance),
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub entity: Box<Account<'info, Entity>>,
     
@@ -295,11 +581,15 @@ pub struct AddComponents<'info>{
     // System is allowed to modify the component it's adding
     // System is a signer
     #[account(
-        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key() && check_sys_registry(&components.iter().map(|tuple| tuple.0.clone() ).collect(), &action_bundle_registration.components)
+        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key() && check_sys_registry(&components.iter().map(|tuple| tuple.0 ).collect(), &action_bundle_registration.components),
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
 
-    pub core_ds: Program<'info, CoreDs>, 
+    pub core_ds: Program<'info, CoreDs>,
+
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
@@ -313,12 +603,14 @@ pub struct RemoveComponent<'info>{
     #[account(
         seeds=[b"registry_signer"],
         bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub registry_config: Account<'info, RegistryConfig>,
 
     #[account(
         mut,
-        constraint = entity.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&entity.instance)
+        constraint = entity.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&entity.instance),
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub entity: Account<'info, Entity>,
     
@@ -327,26 +619,32 @@ pub struct RemoveComponent<'info>{
     // System is allowed to modify the component it's adding
     // System is a signer
     #[account(
-        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key() && check_sys_registry(&components, &action_bundle_registration.components)
+        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key() && check_sys_registry(&components, &action_bundle_registration.components),
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
 
-    pub core_ds: Program<'info, CoreDs>, 
+    pub core_ds: Program<'info, CoreDs>,
+
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
-#[instruction(components: Vec<Pubkey>, data:Vec<Vec<u8>>)]
+#[instruction(components: Vec<(Pubkey, Vec<u8>)>)]
 pub struct ModifyComponent<'info>{
     //Used to Sign Tx for the CPI
     #[account(
         seeds=[b"registry_signer"],
         bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub registry_config: Account<'info, RegistryConfig>,
 
     #[account(
         mut,
-        constraint = entity.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&entity.instance)
+        constraint = entity.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&entity.instance),
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub entity: Account<'info, Entity>,
     
@@ -355,11 +653,52 @@ pub struct ModifyComponent<'info>{
     // System is allowed to modify the component it's adding
     // System is a signer
     #[account(
-        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key() && check_sys_registry(&components, &action_bundle_registration.components)
+        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key() && check_sys_registry(&components.iter().map(|tuple| tuple.0 ).collect(), &action_bundle_registration.components),
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
 
-    pub core_ds: Program<'info, CoreDs>, 
+    pub core_ds: Program<'info, CoreDs>,
+
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(operations: Vec<ComponentOperation>)]
+pub struct MutateComponents<'info>{
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    //Used to Sign Tx for the CPI
+    #[account(
+        seeds=[b"registry_signer"],
+        bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    #[account(
+        mut,
+        constraint = entity.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&entity.instance),
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+    
+    pub action_bundle: Signer<'info>,
+    
+    // Every component touched by an operation has to be registered to the Action Bundle
+    #[account(
+        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key() && check_sys_registry(&operations.iter().map(|op| op.component()).collect(), &action_bundle_registration.components),
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
+
+    pub core_ds: Program<'info, CoreDs>,
+
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
 }
 
 #[derive(Accounts)]
@@ -372,20 +711,27 @@ pub struct RemoveEntity<'info>{
     #[account(
         seeds=[b"registry_signer"],
         bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub registry_config: Account<'info, RegistryConfig>,
 
     #[account(
         mut,
-        constraint = entity.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&entity.instance) && entity.components.len() == 0
+        constraint = entity.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&entity.instance) && entity.components.is_empty(),
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub entity: Account<'info, Entity>,
+    /// CHECK: Checked in CoreDS that no ARCNFT is left
+    pub entity_arcnft: AccountInfo<'info>,
+    /// CHECK: Checked in CoreDS that relations are closed
+    pub entity_relations: AccountInfo<'info>,
     
     pub action_bundle: Signer<'info>,
     
     // ANY registered action_bundle can close an empty entity
     #[account(
-        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key()
+        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key(),
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
     )]
     pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
 
@@ -393,6 +739,329 @@ pub struct RemoveEntity<'info>{
 }
 
 
+#[derive(Accounts)]
+pub struct InitRelations<'info>{
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    //Used to Sign Tx for the CPI
+    #[account(
+        seeds=[b"registry_signer"],
+        bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    #[account(
+        constraint = entity.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&entity.instance),
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+    /// CHECK: Initalized via CPI
+    #[account(mut)]
+    pub entity_relations: AccountInfo<'info>,
+
+    pub action_bundle: Signer<'info>,
+
+    // ANY registered action_bundle can link entities in its instances
+    #[account(
+        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key(),
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
+
+    pub core_ds: Program<'info, CoreDs>, 
+}
+
+// Used for both attach and detach, payer gets refunded on detach
+#[derive(Accounts)]
+pub struct Relate<'info>{
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    //Used to Sign Tx for the CPI
+    #[account(
+        seeds=[b"registry_signer"],
+        bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    #[account(
+        constraint = entity.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&entity.instance),
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+    #[account(
+        constraint = target.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&target.instance),
+        constraint = target.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub target: Box<Account<'info, Entity>>,
+
+    /// CHECK: Checked in CoreDS
+    #[account(mut)]
+    pub entity_relations: AccountInfo<'info>,
+    /// CHECK: Checked in CoreDS
+    #[account(mut)]
+    pub target_relations: AccountInfo<'info>,
+
+    pub action_bundle: Signer<'info>,
+
+    // ANY registered action_bundle can link entities in its instances
+    #[account(
+        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key(),
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
+
+    pub core_ds: Program<'info, CoreDs>, 
+}
+
+#[derive(Accounts)]
+pub struct CloseRelations<'info>{
+    #[account(mut)]
+    pub benefactor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    //Used to Sign Tx for the CPI
+    #[account(
+        seeds=[b"registry_signer"],
+        bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    #[account(
+        constraint = entity.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&entity.instance),
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+    /// CHECK: Closed via CPI
+    #[account(mut)]
+    pub entity_relations: AccountInfo<'info>,
+
+    pub action_bundle: Signer<'info>,
+
+    #[account(
+        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key(),
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
+
+    pub core_ds: Program<'info, CoreDs>, 
+}
+
+#[derive(Accounts)]
+#[instruction(component: Pubkey)]
+pub struct OverflowComponent<'info>{
+    // Pays for the ComponentData account as it's created and written, refunded when it's removed
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    //Used to Sign Tx for the CPI
+    #[account(
+        seeds=[b"registry_signer"],
+        bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    #[account(
+        mut,
+        constraint = entity.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&entity.instance),
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+    /// CHECK: Checked in CoreDS
+    #[account(mut)]
+    pub component_data: AccountInfo<'info>,
+    
+    pub action_bundle: Signer<'info>,
+    
+    // System is allowed to modify the component it's writing
+    #[account(
+        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key() && check_sys_registry(&vec![component], &action_bundle_registration.components),
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
+
+    pub core_ds: Program<'info, CoreDs>,
+
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(component: Pubkey)]
+pub struct UploadComponent<'info>{
+    // Pays for the upload and the Entity growing on commit, gets the upload's rent back on commit or cancel
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    //Used to Sign Tx for the CPI
+    #[account(
+        seeds=[b"registry_signer"],
+        bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    #[account(
+        mut,
+        constraint = entity.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&entity.instance),
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+    /// CHECK: Checked in CoreDS
+    #[account(mut)]
+    pub component_upload: AccountInfo<'info>,
+    
+    pub action_bundle: Signer<'info>,
+    
+    // Checked on every step, not just the commit
+    #[account(
+        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key() && check_sys_registry(&vec![component], &action_bundle_registration.components),
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
+
+    pub core_ds: Program<'info, CoreDs>,
+
+    // Only used by commit_component, which records the mutation
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitCompressedTree<'info>{
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    //Used to Sign Tx for the CPI
+    #[account(
+        seeds=[b"registry_signer"],
+        bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    #[account(
+        constraint = registry_instance.registry.key() == program_id.key(),
+        constraint = registry_instance.version == CORE_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_instance: Account<'info, RegistryInstance>,
+
+    // Trees are a per instance resource, so only the Instance Authority can add them
+    #[account(
+        constraint = instance_authority.instance == registry_instance.instance,
+        constraint = instance_authority.authority == authority.key() @ RegistryError::InvalidInstanceAuthority,
+        constraint = instance_authority.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub instance_authority: Account<'info, InstanceAuthority>,
+    pub authority: Signer<'info>,
+
+    /// CHECK: Initalized via CPI
+    #[account(mut)]
+    pub compressed_tree: AccountInfo<'info>,
+    /// CHECK: Initalized via CPI
+    #[account(mut)]
+    pub merkle_tree: AccountInfo<'info>,
+
+    pub core_ds: Program<'info, CoreDs>,
+    /// CHECK: Checked in CoreDS
+    pub compression_program: AccountInfo<'info>,
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+}
+
+// Component permissions are checked against the compressed entity in the instruction
+#[derive(Accounts)]
+pub struct CompressedEntities<'info>{
+    //Used to Sign Tx for the CPI
+    #[account(
+        seeds=[b"registry_signer"],
+        bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    #[account(
+        constraint = registry_instance.registry.key() == program_id.key() && action_bundle_registration.instances.contains(&registry_instance.instance),
+        constraint = registry_instance.version == CORE_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_instance: Account<'info, RegistryInstance>,
+    /// CHECK: Checked in CoreDS
+    #[account(mut)]
+    pub compressed_tree: AccountInfo<'info>,
+    /// CHECK: Checked in CoreDS
+    #[account(mut)]
+    pub merkle_tree: AccountInfo<'info>,
+
+    pub action_bundle: Signer<'info>,
+    #[account(
+        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key(),
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
+
+    pub core_ds: Program<'info, CoreDs>,
+    /// CHECK: Checked in CoreDS
+    pub compression_program: AccountInfo<'info>,
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+}
+
+// Entities are passed in through remaining_accounts, one per change set
+#[derive(Accounts)]
+pub struct BatchEntities<'info>{
+    // Pays for growing Entities and gets refunded for shrinking them
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    //Used to Sign Tx for the CPI
+    #[account(
+        seeds=[b"registry_signer"],
+        bump,
+        constraint = registry_config.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+
+    pub action_bundle: Signer<'info>,
+
+    // Component and instance permissions are checked against the batch in the instruction
+    #[account(
+        constraint = action_bundle_registration.action_bundle.key() == action_bundle.key(),
+        constraint = action_bundle_registration.version == LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub action_bundle_registration: Account<'info, ActionBundleRegistration>,
+
+    pub core_ds: Program<'info, CoreDs>,
+
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateAccount<'info>{
+    // Pays for the extra layout bytes
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: Any Registry account, its type and layout version are detected from its data
+    #[account(
+        mut,
+        owner = crate::ID
+    )]
+    pub account: UncheckedAccount<'info>,
+}
+
 /*************************************************UTIL Functions */
 
 pub fn check_sys_registry(components: &Vec<Pubkey>, action_bundle_components: &BTreeSet<Pubkey>) -> bool {
@@ -402,4 +1071,164 @@ pub fn check_sys_registry(components: &Vec<Pubkey>, action_bundle_components: &B
         }
     }
     return true;
-}
\ No newline at end of file
+}
+
+// Components the manifest needs the Registry to grant
+pub fn get_write_components(manifest: &ActionBundleManifest) -> BTreeSet<Pubkey> {
+    return manifest.components.iter()
+        .filter(|(_, access)| **access == ComponentAccess::Write)
+        .map(|(component, _)| *component)
+        .collect();
+}
+
+// remaining_accounts holds an (InstanceAuthority, authority) pair per instance, in the same order
+pub fn check_instance_authorities(instances: &[u64], remaining_accounts: &[AccountInfo]) -> Result<()> {
+    require!(remaining_accounts.len() == instances.len() * 2, RegistryError::InvalidInstanceAuthority);
+    for (instance, pair) in instances.iter().zip(remaining_accounts.chunks(2)) {
+        let instance_authority = Account::<InstanceAuthority>::try_from(&pair[0])?;
+        require!(instance_authority.version == LAYOUT_VERSION, MigrationError::OutdatedLayout);
+        require!(instance_authority.instance == *instance, RegistryError::InvalidInstanceAuthority);
+        require!(pair[1].is_signer && pair[1].key() == instance_authority.authority, RegistryError::InvalidInstanceAuthority);
+    }
+    Ok(())
+}
+
+// Checks every Entity in the batch against a single ActionBundleRegistration
+pub fn check_batch(program_id: &Pubkey, entities: &[AccountInfo], components: Vec<Vec<Pubkey>>, action_bundle_registration: &ActionBundleRegistration, expected_revisions: Option<Vec<u64>>) -> Result<()> {
+    require!(entities.len() == components.len(), RegistryError::BatchLengthMismatch);
+    require!(check_sys_registry(&components.concat(), &action_bundle_registration.components), RegistryError::ComponentNotRegistered);
+    if let Some(revisions) = expected_revisions.as_ref() {
+        require!(revisions.len() == entities.len(), RegistryError::BatchLengthMismatch);
+    }
+    let mut keys = BTreeSet::new();
+    for (i, info) in entities.iter().enumerate() {
+        require!(keys.insert(info.key()), RegistryError::DuplicateEntity);
+        let entity: Account<Entity> = Account::try_from(info)?;
+        require!(entity.version == ENTITY_LAYOUT_VERSION, MigrationError::OutdatedLayout);
+        require_keys_eq!(entity.registry, *program_id, RegistryError::EntityRegistryMismatch);
+        require!(action_bundle_registration.instances.contains(&entity.instance), RegistryError::InstanceNotRegistered);
+        check_revision(&entity, expected_revisions.as_ref().map(|revisions| revisions[i]))?;
+    }
+    Ok(())
+}
+
+// Off chain bundles pass the revision they read the Entity at, so they can't overwrite changes made since
+pub fn check_revision(entity: &Entity, expected_revision: Option<u64>) -> Result<()> {
+    if let Some(revision) = expected_revision {
+        require!(entity.revision == revision, RegistryError::StaleEntity);
+    }
+    Ok(())
+}
+
+pub fn get_prefab_components_size(components: &BTreeMap<Pubkey, SerializedComponent>) -> usize {
+    let mut size:usize = 0;
+    for comp in components.values() {
+        size += 32 + SerializedComponent::get_max_size() as usize + comp.data.len();
+    }
+    return size;
+}
+
+// Prefab defaults with the overridden data swapped in
+pub fn get_prefab_components(prefab: &Prefab, overrides: Vec<(Pubkey, Vec<u8>)>) -> Result<BTreeMap<Pubkey, SerializedComponent>> {
+    let mut components = prefab.components.clone();
+    for (component, data) in overrides {
+        let comp = components.get_mut(&component).ok_or(RegistryError::PrefabComponentNotFound)?;
+        require!(data.len() as u64 <= comp.max_size, RegistryError::InvalidDataLength);
+        comp.data = data;
+    }
+    return Ok(components);
+}
+
+/**
+ * Same detection as CoreDS: exact length for fixed size accounts, re-derived PDA for the rest.
+ * Action Bundle Registrations don't store their Registry Instance, so it's passed in as a hint and only trusted if the PDA matches.
+ */
+pub fn get_layout_version(address: &Pubkey, data: &[u8], registry_instance: Option<&Pubkey>) -> Result<u8> {
+    let discriminator = data.get(..8).ok_or(error!(MigrationError::UnknownAccount))?;
+
+    // RegistryConfig (core_ds + components) and InstanceAuthority (instance + authority) are both 40 bytes
+    if discriminator == RegistryConfig::discriminator() || discriminator == InstanceAuthority::discriminator() {
+        return get_fixed_layout_version(data, 32+8);
+    } else if discriminator == ComponentSchema::discriminator() {
+        return get_fixed_layout_version(data, STRING_MAX_SIZE as usize);
+    } else if discriminator == ActionBundleManifest::discriminator() {
+        return get_pda_layout_version(address, data, |fields| {
+            let action_bundle = read_pubkey(fields, 0)?;
+            Some(Pubkey::find_program_address(&[b"action_bundle_manifest", action_bundle.as_ref()], &crate::ID).0)
+        });
+    } else if discriminator == ActionBundleRegistration::discriminator() {
+        let registry_instance = registry_instance.ok_or(error!(MigrationError::UnknownLayout))?;
+        return get_pda_layout_version(address, data, |fields| {
+            let action_bundle = read_pubkey(fields, 0)?;
+            Some(Pubkey::find_program_address(&[b"action_bundle_registration", registry_instance.as_ref(), action_bundle.as_ref()], &crate::ID).0)
+        });
+    } else if discriminator == Prefab::discriminator() {
+        return get_pda_layout_version(address, data, |fields| {
+            let authority = read_pubkey(fields, 0)?;
+            let name_len = u32::from_le_bytes(fields.get(32..36)?.try_into().ok()?) as usize;
+            let name = fields.get(36..36+name_len.min(PREFAB_NAME_MAX_SIZE))?;
+            Some(Pubkey::find_program_address(&[b"prefab", authority.as_ref(), hash(name).as_ref()], &crate::ID).0)
+        });
+    }
+
+    err!(MigrationError::UnknownAccount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(revision: u64) -> Entity {
+        Entity {
+            version: ENTITY_LAYOUT_VERSION,
+            entity_id: 0,
+            instance: 1,
+            registry: crate::ID,
+            revision,
+            components: BTreeMap::new(),
+            component_revisions: BTreeMap::new(),
+        }
+    }
+
+    fn serialize(entity: &Entity) -> Vec<u8> {
+        let mut data = vec![];
+        entity.try_serialize(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn revision_is_only_checked_when_expected() {
+        let entity = entity(3);
+        assert!(check_revision(&entity, None).is_ok());
+        assert!(check_revision(&entity, Some(3)).is_ok());
+        assert_eq!(check_revision(&entity, Some(2)).unwrap_err(), error!(RegistryError::StaleEntity));
+        assert_eq!(check_revision(&entity, Some(4)).unwrap_err(), error!(RegistryError::StaleEntity));
+    }
+
+    #[test]
+    fn batch_checks_each_entity_revision() {
+        let keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let mut lamports = [0u64; 2];
+        let mut data = [serialize(&entity(1)), serialize(&entity(5))];
+        let [first_lamports, second_lamports] = &mut lamports;
+        let [first_data, second_data] = &mut data;
+        let entities = [
+            AccountInfo::new(&keys[0], false, true, first_lamports, first_data, &core_ds::ID, false, 0),
+            AccountInfo::new(&keys[1], false, true, second_lamports, second_data, &core_ds::ID, false, 0),
+        ];
+        let registration = ActionBundleRegistration {
+            version: LAYOUT_VERSION,
+            action_bundle: Pubkey::new_unique(),
+            instances: BTreeSet::from([1]),
+            can_mint: false,
+            components: BTreeSet::new(),
+        };
+        let check = |revisions: Option<Vec<u64>>| check_batch(&crate::ID, &entities, vec![vec![], vec![]], &registration, revisions);
+
+        assert!(check(None).is_ok());
+        assert!(check(Some(vec![1, 5])).is_ok());
+        assert_eq!(check(Some(vec![1, 4])).unwrap_err(), error!(RegistryError::StaleEntity));
+        assert_eq!(check(Some(vec![5, 1])).unwrap_err(), error!(RegistryError::StaleEntity));
+        assert_eq!(check(Some(vec![1])).unwrap_err(), error!(RegistryError::BatchLengthMismatch));
+    }
+}
@@ -1 +1,27 @@
-//use anchor_lang::prelude::*;
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum RegistryError {
+    #[msg("Signer isn't the Instance Authority!")]
+    InvalidInstanceAuthority,
+    #[msg("Action Bundle manifest doesn't declare this instance!")]
+    InstanceNotInManifest,
+    #[msg("Number of changes doesn't match the number of Entities passed in!")]
+    BatchLengthMismatch,
+    #[msg("Entity is passed in more than once!")]
+    DuplicateEntity,
+    #[msg("Entity doesn't belong to this Registry!")]
+    EntityRegistryMismatch,
+    #[msg("Action Bundle isn't registered for the Entity's instance!")]
+    InstanceNotRegistered,
+    #[msg("Action Bundle isn't registered for these components!")]
+    ComponentNotRegistered,
+    #[msg("Prefab name is too long!")]
+    PrefabNameTooLong,
+    #[msg("Override targets a component that isn't in the Prefab!")]
+    PrefabComponentNotFound,
+    #[msg("Component data is larger than its max size!")]
+    InvalidDataLength,
+    #[msg("Entity changed since it was read!")]
+    StaleEntity,
+}
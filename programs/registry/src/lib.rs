@@ -1,6 +1,13 @@
+// Anchor's Error is large by design and every instruction returns it
+#![allow(clippy::result_large_err)]
+// Explicit returns are the house style
+#![allow(clippy::needless_return)]
+
 use anchor_lang::prelude::*;
 use std::collections::{BTreeSet, BTreeMap};
-use core_ds::state::SerializedComponent;
+use core_ds::state::{SerializedComponent, ComponentOperation, Relation, CompressedEntity};
+use core_ds::context::{insert_version_byte, write_layout};
+use core_ds::error::MigrationError;
 
 declare_id!("H5mieGWWK6qukHoNzbR6ysLxReeQC4JHZcNM6JkPQnm3");
 
@@ -13,10 +20,10 @@ pub mod state;
 
 //use account::*;
 use context::*;
-//use constant::*;
-//use error::*;
+use constant::*;
+use error::*;
 //use event::*;
-//use state::*;
+use state::*;
 
 #[program]
 pub mod registry {
@@ -26,6 +33,7 @@ pub mod registry {
     use super::*;
 
     pub fn initalize(ctx:Context<Initialize>, core_ds: Pubkey) -> Result<()> {
+        ctx.accounts.registry_config.version = LAYOUT_VERSION;
         ctx.accounts.registry_config.core_ds = core_ds;
         ctx.accounts.registry_config.components = 0;
         Ok(())
@@ -58,6 +66,7 @@ pub mod registry {
         );
 
         core_ds::cpi::init_registry(register_registry_ctx, ctx.program_id.key(), instance)?;
+        ctx.accounts.instance_authority.version = LAYOUT_VERSION;
         ctx.accounts.instance_authority.instance = instance;
         ctx.accounts.instance_authority.authority = ctx.accounts.payer.key(); // fancier Worlds might have different governance setup for this
         
@@ -68,12 +77,17 @@ pub mod registry {
      * Anyone can register new components as long as they use unique URIs
      */
     pub fn register_component(ctx:Context<RegisterComponent>, schema:String) -> Result<()> {
+        ctx.accounts.component.version = LAYOUT_VERSION;
         ctx.accounts.component.url = schema.clone();
         ctx.accounts.registry_config.components += 1;
         Ok(())
     }
 
+    /**
+     * Registers an Action Bundle on one instance, signed by that instance's authority. Components are granted separately.
+     */
     pub fn register_action_bundle(ctx: Context<RegisterSystem>) -> Result<()> {
+        ctx.accounts.action_bundle_registration.version = LAYOUT_VERSION;
         ctx.accounts.action_bundle_registration.action_bundle = ctx.accounts.action_bundle.key();
         ctx.accounts.action_bundle_registration.instances = BTreeSet::new();
         ctx.accounts.action_bundle_registration.instances.insert(ctx.accounts.registry_instance.instance);
@@ -81,6 +95,9 @@ pub mod registry {
         Ok(())
     }
 
+    /**
+     * Grants components to a registered Action Bundle, signed by the instance authority.
+     */
     pub fn add_components_to_action_bundle_registration(ctx:Context<AddComponentsToActionBundleRegistration>, components:Vec<Pubkey>) -> Result<()> {
         for comp in components {
             ctx.accounts.action_bundle_registration.components.insert(comp);
@@ -88,20 +105,99 @@ pub mod registry {
         Ok(())
     }
 
+    /**
+     * Each added instance has to be approved by its own authority, passed as (InstanceAuthority, authority) pairs in remaining_accounts.
+     */
     pub fn add_instances_to_action_bundle_registration(ctx:Context<AddInstancesToActionBundleRegistration>, instances: Vec<u64>) -> Result<()> {
+        check_instance_authorities(&instances, ctx.remaining_accounts)?;
         for instance in instances{
             ctx.accounts.action_bundle_registration.instances.insert(instance);
         }
         Ok(())
     }
 
+    /**
+     * Action Bundles declare the components (and how they use them), instances and minting they need.
+     * Signed by the Action Bundle itself.
+     */
+    pub fn publish_manifest(ctx:Context<PublishManifest>, components: Vec<(Pubkey, ComponentAccess)>, can_mint: bool, instances: Vec<u64>) -> Result<()> {
+        ctx.accounts.action_bundle_manifest.version = LAYOUT_VERSION;
+        ctx.accounts.action_bundle_manifest.action_bundle = ctx.accounts.action_bundle.key();
+        ctx.accounts.action_bundle_manifest.can_mint = can_mint;
+        ctx.accounts.action_bundle_manifest.instances = instances.into_iter().collect();
+        ctx.accounts.action_bundle_manifest.components = components.into_iter().collect();
+        Ok(())
+    }
+
+    /**
+     * Registers an Action Bundle with exactly the permissions its manifest declares, in one go.
+     */
+    pub fn register_action_bundle_from_manifest(ctx:Context<RegisterFromManifest>) -> Result<()> {
+        let manifest = &ctx.accounts.action_bundle_manifest;
+        ctx.accounts.action_bundle_registration.version = LAYOUT_VERSION;
+        ctx.accounts.action_bundle_registration.action_bundle = ctx.accounts.action_bundle.key();
+        // Only the instance whose authority signed, the manifest constraint already checked it's listed
+        ctx.accounts.action_bundle_registration.instances = BTreeSet::new();
+        ctx.accounts.action_bundle_registration.instances.insert(ctx.accounts.registry_instance.instance);
+        ctx.accounts.action_bundle_registration.can_mint = manifest.can_mint;
+        ctx.accounts.action_bundle_registration.components = get_write_components(manifest);
+        Ok(())
+    }
+
     pub fn init_entity(ctx:Context<InitEntity>, entity_id: u64, components: BTreeMap<Pubkey, SerializedComponent>) -> Result<()> {
         let accounts = core_ds::cpi::accounts::InitEntity {
             entity: ctx.accounts.entity.to_account_info(),
             payer: ctx.accounts.payer.to_account_info(),
             system_program: ctx.accounts.system_program.to_account_info(),
             registry_instance: ctx.accounts.registry_instance.to_account_info(),
+            action_bundle: ctx.accounts.action_bundle.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info(),
+            noop_program: ctx.accounts.noop_program.to_account_info(),
+        };  
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::init_entity(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            accounts,
+            signer_seeds
+        ), entity_id, components)?;
+        
+        Ok(())
+    }
+
+    /**
+     * Anyone can create prefabs, they're namespaced by the authority that creates them
+     */
+    pub fn create_prefab(ctx:Context<CreatePrefab>, name: String, components: BTreeMap<Pubkey, SerializedComponent>) -> Result<()> {
+        for comp in components.values() {
+            require!(comp.data.len() as u64 <= comp.max_size, RegistryError::InvalidDataLength);
+        }
+        ctx.accounts.prefab.version = LAYOUT_VERSION;
+        ctx.accounts.prefab.authority = ctx.accounts.authority.key();
+        ctx.accounts.prefab.name = name;
+        ctx.accounts.prefab.components = components;
+        Ok(())
+    }
+
+    pub fn close_prefab(_ctx:Context<ClosePrefab>) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn init_entity_from_prefab(ctx:Context<InitEntityFromPrefab>, entity_id: u64, overrides: Vec<(Pubkey, Vec<u8>)>) -> Result<()> {
+        let components = get_prefab_components(&ctx.accounts.prefab, overrides)?;
+
+        let accounts = core_ds::cpi::accounts::InitEntity {
+            entity: ctx.accounts.entity.to_account_info(),
+            payer: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            registry_instance: ctx.accounts.registry_instance.to_account_info(),
+            action_bundle: ctx.accounts.action_bundle.to_account_info(),
             registry_signer: ctx.accounts.registry_config.to_account_info(),
+            noop_program: ctx.accounts.noop_program.to_account_info(),
         };  
         let registry_signer_seeds:&[&[u8]] = &[
             b"registry_signer",
@@ -118,6 +214,35 @@ pub mod registry {
         Ok(())
     }
 
+    /**
+     * Copies the source Entity's components (or just the filtered ones) into a new Entity, in the same or another instance
+     */
+    pub fn clone_entity(ctx:Context<CloneEntity>, entity_id: u64, filter: Option<Vec<Pubkey>>) -> Result<()> {
+        let accounts = core_ds::cpi::accounts::CloneEntity {
+            payer: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            source: ctx.accounts.source.to_account_info(),
+            registry_instance: ctx.accounts.registry_instance.to_account_info(),
+            entity: ctx.accounts.entity.to_account_info(),
+            action_bundle: ctx.accounts.action_bundle.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info(),
+            noop_program: ctx.accounts.noop_program.to_account_info(),
+        };  
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::clone_entity(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            accounts,
+            signer_seeds
+        ), entity_id, filter)?;
+        
+        Ok(())
+    }
+
     pub fn mint_arcnft(ctx:Context<MintARCNFT>) -> Result<()> {
         let accounts = core_ds::cpi::accounts::MintARCNFT {
             entity: ctx.accounts.entity.to_account_info(),
@@ -126,7 +251,9 @@ pub mod registry {
             registry_instance: ctx.accounts.registry_instance.to_account_info(),
             registry_signer: ctx.accounts.registry_config.to_account_info(),
             mint: ctx.accounts.mint.to_account_info(),
+            mint_authority: ctx.accounts.action_bundle.to_account_info(),
             arcnft: ctx.accounts.arcnft.to_account_info(),
+            entity_arcnft: ctx.accounts.entity_arcnft.to_account_info(),
         };  
         let registry_signer_seeds:&[&[u8]] = &[
             b"registry_signer",
@@ -143,12 +270,41 @@ pub mod registry {
         Ok(())
     }
 
-    pub fn req_add_component(ctx:Context<AddComponents>, components: Vec<(Pubkey,SerializedComponent)>) -> Result<()> {
+    pub fn burn_arcnft(ctx:Context<BurnARCNFT>) -> Result<()> {
+        let accounts = core_ds::cpi::accounts::BurnARCNFT {
+            benefactor: ctx.accounts.benefactor.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            entity: ctx.accounts.entity.to_account_info(),
+            mint: ctx.accounts.mint.to_account_info(),
+            arcnft: ctx.accounts.arcnft.to_account_info(),
+            entity_arcnft: ctx.accounts.entity_arcnft.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info(),
+        };
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::burn_arcnft(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            accounts,
+            signer_seeds
+        ))?;
+        
+        Ok(())
+    }
+
+    pub fn req_add_component(ctx:Context<AddComponents>, components: Vec<(Pubkey,SerializedComponent)>, expected_revision: Option<u64>) -> Result<()> {
+        check_revision(&ctx.accounts.entity, expected_revision)?;
+
         let accounts = core_ds::cpi::accounts::AddComponent {
             payer: ctx.accounts.payer.to_account_info(),
             system_program: ctx.accounts.system_program.to_account_info(),
             entity: ctx.accounts.entity.to_account_info(),
-            registry_signer: ctx.accounts.registry_config.to_account_info()
+            action_bundle: ctx.accounts.action_bundle.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info(),
+            noop_program: ctx.accounts.noop_program.to_account_info(),
         };
         let registry_signer_seeds:&[&[u8]] = &[
             b"registry_signer",
@@ -164,12 +320,16 @@ pub mod registry {
         Ok(())
     }
 
-    pub fn req_remove_component(ctx:Context<RemoveComponent>, components: Vec<Pubkey>) -> Result<()> {
+    pub fn req_remove_component(ctx:Context<RemoveComponent>, components: Vec<Pubkey>, expected_revision: Option<u64>) -> Result<()> {
+        check_revision(&ctx.accounts.entity, expected_revision)?;
+
         let accounts = core_ds::cpi::accounts::RemoveComponent {
             benefactor: ctx.accounts.benefactor.to_account_info(),
             system_program: ctx.accounts.system_program.to_account_info(),
             entity: ctx.accounts.entity.to_account_info(),
-            registry_signer: ctx.accounts.registry_config.to_account_info()
+            action_bundle: ctx.accounts.action_bundle.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info(),
+            noop_program: ctx.accounts.noop_program.to_account_info(),
         };
         let registry_signer_seeds:&[&[u8]] = &[
             b"registry_signer",
@@ -185,10 +345,14 @@ pub mod registry {
         Ok(())
     }
 
-    pub fn req_modify_component(ctx:Context<ModifyComponent>, components: Vec<(Pubkey, Vec<u8>)>) -> Result<()> {
+    pub fn req_modify_component(ctx:Context<ModifyComponent>, components: Vec<(Pubkey, Vec<u8>)>, expected_revision: Option<u64>) -> Result<()> {
+        check_revision(&ctx.accounts.entity, expected_revision)?;
+
         let accounts = core_ds::cpi::accounts::ModifyComponent {
             entity: ctx.accounts.entity.to_account_info(),
-            registry_signer: ctx.accounts.registry_config.to_account_info()
+            action_bundle: ctx.accounts.action_bundle.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info(),
+            noop_program: ctx.accounts.noop_program.to_account_info(),
         };
         let registry_signer_seeds:&[&[u8]] = &[
             b"registry_signer",
@@ -205,11 +369,459 @@ pub mod registry {
         Ok(())
     }
 
-    pub fn req_remove_entity(ctx:Context<RemoveEntity>) -> Result<()> {
+    pub fn req_mutate_components(ctx:Context<MutateComponents>, operations: Vec<ComponentOperation>, expected_revision: Option<u64>) -> Result<()> {
+        check_revision(&ctx.accounts.entity, expected_revision)?;
+
+        let accounts = core_ds::cpi::accounts::MutateComponents {
+            payer: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            entity: ctx.accounts.entity.to_account_info(),
+            action_bundle: ctx.accounts.action_bundle.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info(),
+            noop_program: ctx.accounts.noop_program.to_account_info(),
+        };
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::mutate_components(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            accounts,
+            signer_seeds
+        ), operations)?;
+        Ok(())
+    }
+
+    /**
+     * Large components are written in chunks to their own ComponentData account, see CoreDS
+     */
+    pub fn req_add_overflow_component(ctx:Context<OverflowComponent>, component: Pubkey, max_size: u64, expected_revision: Option<u64>) -> Result<()> {
+        check_revision(&ctx.accounts.entity, expected_revision)?;
+
+        let accounts = core_ds::cpi::accounts::AddOverflowComponent {
+            payer: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            entity: ctx.accounts.entity.to_account_info(),
+            component_data: ctx.accounts.component_data.to_account_info(),
+            action_bundle: ctx.accounts.action_bundle.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info(),
+            noop_program: ctx.accounts.noop_program.to_account_info(),
+        };
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::add_overflow_component(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            accounts,
+            signer_seeds
+        ), component, max_size)?;
+        Ok(())
+    }
+
+    pub fn req_write_component_data(ctx:Context<OverflowComponent>, component: Pubkey, offset: u64, data: Vec<u8>, expected_revision: Option<u64>) -> Result<()> {
+        check_revision(&ctx.accounts.entity, expected_revision)?;
+
+        let accounts = core_ds::cpi::accounts::WriteComponentData {
+            payer: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            entity: ctx.accounts.entity.to_account_info(),
+            component_data: ctx.accounts.component_data.to_account_info(),
+            action_bundle: ctx.accounts.action_bundle.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info(),
+            noop_program: ctx.accounts.noop_program.to_account_info(),
+        };
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::write_component_data(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            accounts,
+            signer_seeds
+        ), component, offset, data)?;
+        Ok(())
+    }
+
+    pub fn req_remove_overflow_component(ctx:Context<OverflowComponent>, component: Pubkey, expected_revision: Option<u64>) -> Result<()> {
+        check_revision(&ctx.accounts.entity, expected_revision)?;
+
+        let accounts = core_ds::cpi::accounts::RemoveOverflowComponent {
+            benefactor: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            entity: ctx.accounts.entity.to_account_info(),
+            component_data: ctx.accounts.component_data.to_account_info(),
+            action_bundle: ctx.accounts.action_bundle.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info(),
+            noop_program: ctx.accounts.noop_program.to_account_info(),
+        };
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::remove_overflow_component(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            accounts,
+            signer_seeds
+        ), component)?;
+        Ok(())
+    }
+
+    /**
+     * Staged upload of a component in chunks, see CoreDS. Permissions are checked on every step
+     */
+    pub fn req_allocate_component(ctx:Context<UploadComponent>, component: Pubkey, max_size: u64) -> Result<()> {
+        let accounts = core_ds::cpi::accounts::AllocateComponent {
+            payer: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            entity: ctx.accounts.entity.to_account_info(),
+            component_upload: ctx.accounts.component_upload.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info()
+        };
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::allocate_component(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            accounts,
+            signer_seeds
+        ), component, max_size)?;
+        Ok(())
+    }
+
+    pub fn req_write_component_chunk(ctx:Context<UploadComponent>, component: Pubkey, offset: u64, data: Vec<u8>) -> Result<()> {
+        let accounts = core_ds::cpi::accounts::WriteComponentChunk {
+            entity: ctx.accounts.entity.to_account_info(),
+            component_upload: ctx.accounts.component_upload.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info()
+        };
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::write_component_chunk(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            accounts,
+            signer_seeds
+        ), component, offset, data)?;
+        Ok(())
+    }
+
+    pub fn req_commit_component(ctx:Context<UploadComponent>, component: Pubkey, expected_revision: Option<u64>) -> Result<()> {
+        check_revision(&ctx.accounts.entity, expected_revision)?;
+
+        let accounts = core_ds::cpi::accounts::CommitComponent {
+            payer: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            entity: ctx.accounts.entity.to_account_info(),
+            component_upload: ctx.accounts.component_upload.to_account_info(),
+            action_bundle: ctx.accounts.action_bundle.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info(),
+            noop_program: ctx.accounts.noop_program.to_account_info(),
+        };
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::commit_component(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            accounts,
+            signer_seeds
+        ), component)?;
+        Ok(())
+    }
+
+    pub fn req_cancel_component(ctx:Context<UploadComponent>, component: Pubkey) -> Result<()> {
+        let accounts = core_ds::cpi::accounts::CancelComponent {
+            benefactor: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            entity: ctx.accounts.entity.to_account_info(),
+            component_upload: ctx.accounts.component_upload.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info()
+        };
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::cancel_component(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            accounts,
+            signer_seeds
+        ), component)?;
+        Ok(())
+    }
+
+    /**
+     * Compressed Entities, see CoreDS. Proofs are passed through as remaining_accounts
+     */
+    pub fn req_init_compressed_tree(ctx:Context<InitCompressedTree>, max_depth: u32, max_buffer_size: u32) -> Result<()> {
+        let accounts = core_ds::cpi::accounts::InitCompressedTree {
+            payer: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            registry_instance: ctx.accounts.registry_instance.to_account_info(),
+            compressed_tree: ctx.accounts.compressed_tree.to_account_info(),
+            merkle_tree: ctx.accounts.merkle_tree.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info(),
+            compression_program: ctx.accounts.compression_program.to_account_info(),
+            noop_program: ctx.accounts.noop_program.to_account_info(),
+        };
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::init_compressed_tree(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            accounts,
+            signer_seeds
+        ), max_depth, max_buffer_size)?;
+        Ok(())
+    }
+
+    pub fn req_init_compressed_entity(ctx:Context<CompressedEntities>, entity_id: u64, components: BTreeMap<Pubkey, SerializedComponent>) -> Result<()> {
+        require!(check_sys_registry(&components.keys().cloned().collect(), &ctx.accounts.action_bundle_registration.components), RegistryError::ComponentNotRegistered);
+
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::init_compressed_entity(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            get_compressed_accounts(ctx.accounts),
+            signer_seeds
+        ), entity_id, components)?;
+        Ok(())
+    }
+
+    pub fn req_modify_compressed_entity<'info>(ctx:Context<'_, '_, '_, 'info, CompressedEntities<'info>>, root: [u8; 32], entity: CompressedEntity, index: u32, operations: Vec<ComponentOperation>) -> Result<()> {
+        require!(check_sys_registry(&operations.iter().map(|op| op.component()).collect(), &ctx.accounts.action_bundle_registration.components), RegistryError::ComponentNotRegistered);
+
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::modify_compressed_entity(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            get_compressed_accounts(ctx.accounts),
+            signer_seeds
+        ).with_remaining_accounts(ctx.remaining_accounts.to_vec()), root, entity, index, operations)?;
+        Ok(())
+    }
+
+    // Unlike regular Entities, compressed ones don't have to be emptied first, the bundle just needs permissions for what's left
+    pub fn req_remove_compressed_entity<'info>(ctx:Context<'_, '_, '_, 'info, CompressedEntities<'info>>, root: [u8; 32], entity: CompressedEntity, index: u32) -> Result<()> {
+        require!(check_sys_registry(&entity.components.keys().cloned().collect(), &ctx.accounts.action_bundle_registration.components), RegistryError::ComponentNotRegistered);
+
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::remove_compressed_entity(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            get_compressed_accounts(ctx.accounts),
+            signer_seeds
+        ).with_remaining_accounts(ctx.remaining_accounts.to_vec()), root, entity, index)?;
+        Ok(())
+    }
+
+    /**
+     * Batch variants take the Entities as remaining_accounts, changes[i] applies to remaining_accounts[i]
+     * Registration is checked once for the whole batch, then handed to CoreDS in a single CPI
+     */
+    pub fn req_batch_add_components<'info>(ctx:Context<'_, '_, '_, 'info, BatchEntities<'info>>, changes: Vec<Vec<(Pubkey,SerializedComponent)>>, expected_revisions: Option<Vec<u64>>) -> Result<()> {
+        check_batch(
+            ctx.program_id,
+            ctx.remaining_accounts,
+            changes.iter().map(|components| components.iter().map(|tuple| tuple.0).collect()).collect(),
+            &ctx.accounts.action_bundle_registration,
+            expected_revisions
+        )?;
+
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+
+        core_ds::cpi::batch_add_components(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            get_batch_accounts(ctx.accounts),
+            signer_seeds
+        ).with_remaining_accounts(ctx.remaining_accounts.to_vec()), changes)?;
+        Ok(())
+    }
+
+    pub fn req_batch_remove_components<'info>(ctx:Context<'_, '_, '_, 'info, BatchEntities<'info>>, changes: Vec<Vec<Pubkey>>, expected_revisions: Option<Vec<u64>>) -> Result<()> {
+        check_batch(
+            ctx.program_id,
+            ctx.remaining_accounts,
+            changes.clone(),
+            &ctx.accounts.action_bundle_registration,
+            expected_revisions
+        )?;
+
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+
+        core_ds::cpi::batch_remove_components(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            get_batch_accounts(ctx.accounts),
+            signer_seeds
+        ).with_remaining_accounts(ctx.remaining_accounts.to_vec()), changes)?;
+        Ok(())
+    }
+
+    pub fn req_batch_modify_components<'info>(ctx:Context<'_, '_, '_, 'info, BatchEntities<'info>>, changes: Vec<Vec<(Pubkey, Vec<u8>)>>, expected_revisions: Option<Vec<u64>>) -> Result<()> {
+        check_batch(
+            ctx.program_id,
+            ctx.remaining_accounts,
+            changes.iter().map(|components| components.iter().map(|tuple| tuple.0).collect()).collect(),
+            &ctx.accounts.action_bundle_registration,
+            expected_revisions
+        )?;
+
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+
+        core_ds::cpi::batch_modify_components(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            get_batch_accounts(ctx.accounts),
+            signer_seeds
+        ).with_remaining_accounts(ctx.remaining_accounts.to_vec()), changes)?;
+        Ok(())
+    }
+
+    pub fn req_init_relations(ctx:Context<InitRelations>) -> Result<()> {
+        let accounts = core_ds::cpi::accounts::InitRelations {
+            payer: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            entity: ctx.accounts.entity.to_account_info(),
+            entity_relations: ctx.accounts.entity_relations.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info()
+        };
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::init_relations(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            accounts,
+            signer_seeds
+        ))?;
+        Ok(())
+    }
+
+    pub fn req_attach(ctx:Context<Relate>, relation: Relation) -> Result<()> {
+        let accounts = core_ds::cpi::accounts::Attach {
+            payer: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            entity: ctx.accounts.entity.to_account_info(),
+            target: ctx.accounts.target.to_account_info(),
+            entity_relations: ctx.accounts.entity_relations.to_account_info(),
+            target_relations: ctx.accounts.target_relations.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info()
+        };
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::attach(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            accounts,
+            signer_seeds
+        ), relation)?;
+        Ok(())
+    }
+
+    pub fn req_detach(ctx:Context<Relate>, relation: Relation) -> Result<()> {
+        let accounts = core_ds::cpi::accounts::Detach {
+            benefactor: ctx.accounts.payer.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            entity: ctx.accounts.entity.to_account_info(),
+            target: ctx.accounts.target.to_account_info(),
+            entity_relations: ctx.accounts.entity_relations.to_account_info(),
+            target_relations: ctx.accounts.target_relations.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info()
+        };
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::detach(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            accounts,
+            signer_seeds
+        ), relation)?;
+        Ok(())
+    }
+
+    pub fn req_close_relations(ctx:Context<CloseRelations>) -> Result<()> {
+        let accounts = core_ds::cpi::accounts::CloseRelations {
+            benefactor: ctx.accounts.benefactor.to_account_info(),
+            system_program: ctx.accounts.system_program.to_account_info(),
+            entity: ctx.accounts.entity.to_account_info(),
+            entity_relations: ctx.accounts.entity_relations.to_account_info(),
+            registry_signer: ctx.accounts.registry_config.to_account_info()
+        };
+        let registry_signer_seeds:&[&[u8]] = &[
+            b"registry_signer",
+            &[*ctx.bumps.get("registry_config").unwrap()]
+        ];
+        let signer_seeds = &[registry_signer_seeds];
+        
+        core_ds::cpi::close_relations(CpiContext::new_with_signer(
+            ctx.accounts.core_ds.to_account_info(),
+            accounts,
+            signer_seeds
+        ))?;
+        Ok(())
+    }
+
+    pub fn req_remove_entity(ctx:Context<RemoveEntity>, expected_revision: Option<u64>) -> Result<()> {
+        check_revision(&ctx.accounts.entity, expected_revision)?;
+
         let accounts = core_ds::cpi::accounts::RemoveEntity {
             benefactor: ctx.accounts.benefactor.to_account_info(),
             system_program: ctx.accounts.system_program.to_account_info(),
             entity: ctx.accounts.entity.to_account_info(),
+            entity_arcnft: ctx.accounts.entity_arcnft.to_account_info(),
+            entity_relations: ctx.accounts.entity_relations.to_account_info(),
             registry_signer: ctx.accounts.registry_config.to_account_info()
         };
         let registry_signer_seeds:&[&[u8]] = &[
@@ -227,4 +839,41 @@ pub mod registry {
         Ok(())
     }
 
-}
\ No newline at end of file
+    /**
+     * Upgrades any Registry account to the latest layout in place, CoreDS accounts migrate through CoreDS directly.
+     * Action Bundle Registrations need their Registry Instance as the first remaining account.
+     */
+    pub fn migrate_account<'info>(ctx:Context<'_, '_, '_, 'info, MigrateAccount<'info>>) -> Result<()> {
+        let account = ctx.accounts.account.to_account_info();
+        let registry_instance = ctx.remaining_accounts.first().map(|info| info.key);
+        let upgraded = {
+            let data = account.try_borrow_data()?;
+            let version = get_layout_version(account.key, &data, registry_instance)?;
+            require!(version < LAYOUT_VERSION, MigrationError::AlreadyCurrent);
+            insert_version_byte(&data, LAYOUT_VERSION)
+        };
+        write_layout(&account, &upgraded, &ctx.accounts.payer.to_account_info(), &ctx.accounts.system_program.to_account_info())
+    }
+
+}
+
+pub fn get_batch_accounts<'info>(accounts: &BatchEntities<'info>) -> core_ds::cpi::accounts::BatchEntities<'info> {
+    return core_ds::cpi::accounts::BatchEntities {
+        payer: accounts.payer.to_account_info(),
+        system_program: accounts.system_program.to_account_info(),
+        action_bundle: accounts.action_bundle.to_account_info(),
+        registry_signer: accounts.registry_config.to_account_info(),
+        noop_program: accounts.noop_program.to_account_info(),
+    };
+}
+
+pub fn get_compressed_accounts<'info>(accounts: &CompressedEntities<'info>) -> core_ds::cpi::accounts::CompressedEntities<'info> {
+    return core_ds::cpi::accounts::CompressedEntities {
+        registry_instance: accounts.registry_instance.to_account_info(),
+        compressed_tree: accounts.compressed_tree.to_account_info(),
+        merkle_tree: accounts.merkle_tree.to_account_info(),
+        registry_signer: accounts.registry_config.to_account_info(),
+        compression_program: accounts.compression_program.to_account_info(),
+        noop_program: accounts.noop_program.to_account_info(),
+    };
+}
@@ -1,2 +1,8 @@
-//use anchor_lang::prelude::*;
+use anchor_lang::prelude::*;
 
+// How an Action Bundle uses a component. Only Write needs to be granted by the Registry, Read is declared so others know what a bundle depends on.
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentAccess {
+    Read,
+    Write,
+}
@@ -1,7 +1,10 @@
 use std::collections::BTreeMap;
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
 use core_ds::account::MaxSize;
 
+use crate::error::TSABError;
+
 #[account]
 pub struct TSABConfig {
     pub authority: Pubkey,
@@ -13,4 +16,20 @@ impl MaxSize for TSABConfig {
     fn get_max_size() -> u64 {
         return 32+4;
     }
+}
+
+impl TSABConfig {
+    pub fn get_component(&self, name: &str) -> Result<Pubkey> {
+        match self.components.get(&get_component_hash(name)) {
+            Some(component) => Ok(*component),
+            None => {
+                msg!("TSAB isn't configured with a {} component", name);
+                err!(TSABError::ComponentNotConfigured)
+            }
+        }
+    }
+}
+
+pub fn get_component_hash(name: &str) -> [u8;32] {
+    return hash(name.as_bytes()).to_bytes();
 }
\ No newline at end of file
@@ -1,7 +1,10 @@
 use anchor_lang::prelude::*;
+use std::collections::BTreeSet;
+use mpl_token_metadata::state::{Creator as MetaplexCreator, DataV2, Metadata};
 
 use core_ds::account::MaxSize;
 use crate::constant::*;
+use crate::error::TSABError;
 
 #[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
 pub struct ComponentMetadata{
@@ -15,6 +18,206 @@ pub struct ComponentMetadata{
 
 impl MaxSize for ComponentMetadata {
     fn get_max_size() -> u64 {
-        return 32 + 32 + METADATA_NAME_MAX_SIZE + METADATA_SYMBOL_MAX_SIZE + METADATA_URI_MAX_SIZE + 1;
+        return 32 + 32 + (STRING_PREFIX_SIZE + METADATA_NAME_MAX_SIZE) + (STRING_PREFIX_SIZE + METADATA_SYMBOL_MAX_SIZE) + (STRING_PREFIX_SIZE + METADATA_URI_MAX_SIZE) + 1;
+    }
+}
+
+impl ComponentMetadata {
+    pub fn validate(&self) -> Result<()> {
+        require!(self.name.len() as u64 <= METADATA_NAME_MAX_SIZE, TSABError::NameTooLong);
+        require!(self.symbol.len() as u64 <= METADATA_SYMBOL_MAX_SIZE, TSABError::SymbolTooLong);
+        require!(self.uri.len() as u64 <= METADATA_URI_MAX_SIZE, TSABError::UriTooLong);
+        Ok(())
+    }
+
+    // Entities created before the Metaplex limits keep the smaller max_size they were allocated with
+    pub fn serialize_within(&self, max_size: u64) -> Result<Vec<u8>> {
+        let data = self.try_to_vec().unwrap();
+        require!(data.len() as u64 <= max_size, TSABError::MetadataExceedsComponentSize);
+        Ok(data)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct ComponentCollection{
+    // Entity holding the collection's Metadata Component
+    pub collection: Pubkey,
+    pub verified: bool,
+}
+
+impl MaxSize for ComponentCollection {
+    fn get_max_size() -> u64 {
+        return 32 + 1;
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct Creator{
+    pub address: Pubkey,
+    pub share: u8,
+    pub verified: bool,
+}
+
+impl MaxSize for Creator {
+    fn get_max_size() -> u64 {
+        return 32 + 1 + 1;
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct ComponentCreators{
+    pub creators: Vec<Creator>,
+}
+
+impl MaxSize for ComponentCreators {
+    fn get_max_size() -> u64 {
+        return VEC_PREFIX_SIZE + CREATORS_MAX_COUNT * Creator::get_max_size();
+    }
+}
+
+impl ComponentCreators {
+    pub fn validate(&self) -> Result<()> {
+        require!(self.creators.len() as u64 <= CREATORS_MAX_COUNT, TSABError::TooManyCreators);
+        let total_share:u64 = self.creators.iter().map(|creator| creator.share as u64).sum();
+        require!(self.creators.is_empty() || total_share == 100, TSABError::InvalidCreatorShares);
+        // Metaplex rejects duplicates, so they couldn't be mirrored
+        let mut addresses = BTreeSet::new();
+        require!(self.creators.iter().all(|creator| addresses.insert(creator.address)), TSABError::DuplicateCreator);
+        Ok(())
+    }
+
+    /// Creators list set by the update authority. Creators who verified, on the Entity or on Metaplex, stay verified
+    /// and can't be dropped, since Metaplex would reject the update. The signing update authority is verified right away.
+    pub fn from_shares(creators: &[(Pubkey, u8)], verified: &BTreeSet<Pubkey>, update_authority: &Pubkey) -> Result<Self> {
+        let component = ComponentCreators {
+            creators: creators.iter().map(|(address, share)| Creator {
+                address: *address,
+                share: *share,
+                verified: address == update_authority || verified.contains(address)
+            }).collect()
+        };
+        component.validate()?;
+        for address in verified {
+            require!(component.creators.iter().any(|creator| creator.address == *address), TSABError::VerifiedCreatorRemoved);
+        }
+        Ok(component)
+    }
+}
+
+/// Creators verified on the Entity's Creators Component or on its Metaplex mirror
+pub fn get_verified_creators(current: Option<&ComponentCreators>, metaplex: Option<&Metadata>) -> BTreeSet<Pubkey> {
+    let arc = current.iter().flat_map(|component| component.creators.iter())
+        .filter(|creator| creator.verified)
+        .map(|creator| creator.address);
+    let metaplex = metaplex.and_then(|metadata| metadata.data.creators.as_ref()).into_iter().flatten()
+        .filter(|creator| creator.verified)
+        .map(|creator| creator.address);
+    return arc.chain(metaplex).collect();
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct ComponentSupply{
+    pub mint: Pubkey,
+    pub decimals: u8,
+    // Kept in sync with the SPL Mint supply
+    pub supply: u64,
+    pub max_supply: Option<u64>,
+}
+
+impl MaxSize for ComponentSupply {
+    fn get_max_size() -> u64 {
+        return 32 + 1 + 8 + (1 + 8);
+    }
+}
+
+/// Metaplex view of the Metadata and Creators Components, on top of the existing Metaplex data if there is any.
+/// TSAB doesn't track royalties, so they're kept. Creators verify on Metaplex with their own signature,
+/// so each creator keeps the verification Metaplex already has for it.
+pub fn get_metaplex_data(metadata: &ComponentMetadata, creators: Option<&ComponentCreators>, existing: Option<&Metadata>) -> DataV2 {
+    let existing_creators = existing.and_then(|existing| existing.data.creators.clone());
+    let creators = match creators {
+        // Metaplex takes no creators as None, not as an empty list
+        Some(component) if component.creators.is_empty() => None,
+        Some(component) => Some(component.creators.iter().map(|creator| MetaplexCreator {
+            address: creator.address,
+            verified: existing_creators.iter().flatten().any(|existing| existing.address == creator.address && existing.verified),
+            share: creator.share,
+        }).collect()),
+        None => existing_creators,
+    };
+    return DataV2 {
+        name: metadata.name.clone(),
+        symbol: metadata.symbol.clone(),
+        uri: metadata.uri.clone(),
+        seller_fee_basis_points: existing.map_or(0, |existing| existing.data.seller_fee_basis_points),
+        creators,
+        collection: None,
+        uses: None,
+    };
+}
+
+/// Metadata Component built from an existing Metaplex Metadata account
+pub fn get_component_metadata(metadata: &Metadata) -> ComponentMetadata {
+    // Metaplex pads strings with null bytes up to their max length
+    return ComponentMetadata {
+        update_authority: metadata.update_authority,
+        mint: metadata.mint,
+        name: metadata.data.name.trim_end_matches(char::from(0)).to_string(),
+        symbol: metadata.data.symbol.trim_end_matches(char::from(0)).to_string(),
+        uri: metadata.data.uri.trim_end_matches(char::from(0)).to_string(),
+        is_mutable: metadata.is_mutable,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn creator(address: Pubkey, share: u8, verified: bool) -> Creator {
+        Creator { address, share, verified }
+    }
+
+    #[test]
+    fn verified_creators_stay_verified() {
+        let (authority, signed, other) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let current = ComponentCreators { creators: vec![creator(signed, 50, true), creator(other, 50, false)] };
+        let verified = get_verified_creators(Some(&current), None);
+        assert_eq!(verified, BTreeSet::from([signed]));
+
+        let component = ComponentCreators::from_shares(&[(signed, 20), (authority, 30), (other, 50)], &verified, &authority).unwrap();
+        let flags: Vec<(Pubkey, u8, bool)> = component.creators.iter().map(|creator| (creator.address, creator.share, creator.verified)).collect();
+        assert_eq!(flags, vec![(signed, 20, true), (authority, 30, true), (other, 50, false)]);
+
+        // Unverified creators can be dropped
+        assert!(ComponentCreators::from_shares(&[(signed, 100)], &verified, &authority).is_ok());
+    }
+
+    #[test]
+    fn verified_creators_cant_be_removed() {
+        let (authority, signed) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let verified = BTreeSet::from([signed]);
+
+        assert_eq!(ComponentCreators::from_shares(&[(authority, 100)], &verified, &authority).unwrap_err(), error!(TSABError::VerifiedCreatorRemoved));
+        assert_eq!(ComponentCreators::from_shares(&[], &verified, &authority).unwrap_err(), error!(TSABError::VerifiedCreatorRemoved));
+        assert_eq!(ComponentCreators::from_shares(&[(signed, 50)], &verified, &authority).unwrap_err(), error!(TSABError::InvalidCreatorShares));
+    }
+
+    #[test]
+    fn metadata_must_fit_stored_max_size() {
+        let metadata = ComponentMetadata {
+            update_authority: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            name: "Name".to_string(),
+            symbol: "SYM".to_string(),
+            uri: "u".repeat(METADATA_URI_MAX_SIZE as usize),
+            is_mutable: true,
+        };
+        metadata.validate().unwrap();
+
+        let data = metadata.serialize_within(ComponentMetadata::get_max_size()).unwrap();
+        assert_eq!(data, metadata.try_to_vec().unwrap());
+        // Old Entities were allocated with 32 byte strings and no length prefixes
+        let old_max_size = 32 + 32 + 32 + 32 + 32 + 1;
+        assert_eq!(metadata.serialize_within(old_max_size).unwrap_err(), error!(TSABError::MetadataExceedsComponentSize));
     }
 }
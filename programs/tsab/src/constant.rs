@@ -1,3 +1,22 @@
+// Same limits as Metaplex Token Metadata
 pub const METADATA_NAME_MAX_SIZE:u64 = 32;
-pub const METADATA_SYMBOL_MAX_SIZE:u64 = 32;
-pub const METADATA_URI_MAX_SIZE:u64 = 32;
\ No newline at end of file
+pub const METADATA_SYMBOL_MAX_SIZE:u64 = 10;
+pub const METADATA_URI_MAX_SIZE:u64 = 200;
+// Borsh prefixes every String with its u32 length
+pub const STRING_PREFIX_SIZE:u64 = 4;
+// Same creator limit as Metaplex Token Metadata
+pub const CREATORS_MAX_COUNT:u64 = 5;
+// Borsh prefixes every Vec with its u32 length
+pub const VEC_PREFIX_SIZE:u64 = 4;
+
+// Component names, TSABConfig.components is keyed by their hash
+pub const COMPONENT_METADATA:&str = "metadata";
+pub const COMPONENT_COLLECTION:&str = "collection";
+pub const COMPONENT_CREATORS:&str = "creators";
+pub const COMPONENT_SUPPLY:&str = "supply";
+// Schemas TSAB registers for its components. The Registry uses them as seeds, so they have to fit in 32 bytes
+pub const SCHEMA_METADATA:&str = "arc/tsab/metadata";
+pub const SCHEMA_COLLECTION:&str = "arc/tsab/collection";
+pub const SCHEMA_CREATORS:&str = "arc/tsab/creators";
+pub const SCHEMA_SUPPLY:&str = "arc/tsab/supply";
+pub const TSAB_COMPONENT_COUNT:u64 = 4;
\ No newline at end of file
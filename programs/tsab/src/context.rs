@@ -1,23 +1,117 @@
 use anchor_lang::prelude::*;
 
-use anchor_spl::token::Mint;
-use core_ds::account::{MaxSize, RegistryInstance};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::metadata::{Metadata, MetadataAccount};
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use core_ds::account::{MaxSize, RegistryInstance, Entity, ARCNFT};
+use core_ds::constant::{SEEDS_ARCNFT_PREFIX, ENTITY_LAYOUT_VERSION, LAYOUT_VERSION as CORE_LAYOUT_VERSION};
+use core_ds::error::MigrationError;
 use core_ds::program::CoreDs;
 use registry::account::{RegistryConfig, ActionBundleRegistration};
+use registry::constant::LAYOUT_VERSION as REGISTRY_LAYOUT_VERSION;
 use registry::program::Registry;
-use crate::account::TSABConfig;
+use crate::account::{TSABConfig, get_component_hash};
+use crate::constant::TSAB_COMPONENT_COUNT;
+use crate::component::ComponentMetadata;
+use crate::error::TSABError;
 
 
 #[derive(Accounts)]
 pub struct Initialize<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
-    pub system_program: Signer<'info>,
+    pub system_program: Program<'info, System>,
 
     #[account(
         init,
         payer=payer,
-        space=8+TSABConfig::get_max_size() as usize,
+        space=8+TSABConfig::get_max_size() as usize+(TSAB_COMPONENT_COUNT as usize*(32+32)),
+        seeds=[
+            b"tsab_signer"
+        ],
+        bump,
+    )]
+    pub tsab_config: Account<'info, TSABConfig>,
+
+    // Registry Accounts
+    //// Registry Config
+    #[account(
+        mut,
+        constraint = registry_config.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    //// Registry Program
+    pub registry_program: Program<'info, Registry>,
+
+    // Component Schemas, created via CPI unless someone already registered them
+    /// CHECK: Created via CPI
+    #[account(mut)]
+    pub metadata_component: AccountInfo<'info>,
+    /// CHECK: Created via CPI
+    #[account(mut)]
+    pub collection_component: AccountInfo<'info>,
+    /// CHECK: Created via CPI
+    #[account(mut)]
+    pub creators_component: AccountInfo<'info>,
+    /// CHECK: Created via CPI
+    #[account(mut)]
+    pub supply_component: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct AddComponentMapping<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    pub authority: Signer<'info>,
+
+    // Only grows if it's a new mapping, otherwise the old one is overwritten
+    #[account(
+        mut,
+        has_one = authority @ TSABError::InvalidAuthority,
+        realloc = tsab_config.to_account_info().data_len() + if tsab_config.components.contains_key(&get_component_hash(&name)) { 0 } else { 32 + 32 },
+        realloc::payer = payer,
+        realloc::zero = false,
+        seeds=[
+            b"tsab_signer"
+        ],
+        bump,
+    )]
+    pub tsab_config: Account<'info, TSABConfig>,
+}
+
+#[derive(Accounts)]
+#[instruction(name: String)]
+pub struct RemoveComponentMapping<'info> {
+    #[account(mut)]
+    pub benefactor: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority @ TSABError::InvalidAuthority,
+        realloc = tsab_config.to_account_info().data_len() - if tsab_config.components.contains_key(&get_component_hash(&name)) { 32 + 32 } else { 0 },
+        realloc::payer = benefactor,
+        realloc::zero = false,
+        seeds=[
+            b"tsab_signer"
+        ],
+        bump,
+    )]
+    pub tsab_config: Account<'info, TSABConfig>,
+}
+
+#[derive(Accounts)]
+pub struct SetAuthority<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority @ TSABError::InvalidAuthority,
         seeds=[
             b"tsab_signer"
         ],
@@ -30,34 +124,744 @@ pub struct Initialize<'info> {
 pub struct MintMetdata<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
-    pub system_program: Signer<'info>,
+    pub system_program: Program<'info, System>,
 
     //SPL Mint
+    //// New 0 decimal mint, TSAB holds the mint authority until it's revoked in the instruction
+    #[account(
+        init,
+        payer=payer,
+        mint::decimals = 0,
+        mint::authority = tsab_config,
+    )]
     pub mint: Account<'info, Mint>,
+    //// Wallet receiving the NFT
+    /// CHECK: Any account can own the NFT
+    pub recipient: AccountInfo<'info>,
+    #[account(
+        init,
+        payer=payer,
+        associated_token::mint = mint,
+        associated_token::authority = recipient,
+    )]
+    pub recipient_ata: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    //Metaplex Accounts
+    //// Only created if all of them are passed in
+    /// CHECK: Created via CPI to Token Metadata
+    #[account(
+        mut,
+        seeds=[
+            mpl_token_metadata::state::PREFIX.as_bytes(),
+            mpl_token_metadata::ID.as_ref(),
+            mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metaplex_metadata: Option<UncheckedAccount<'info>>,
+    /// CHECK: Created via CPI to Token Metadata
+    #[account(
+        mut,
+        seeds=[
+            mpl_token_metadata::state::PREFIX.as_bytes(),
+            mpl_token_metadata::ID.as_ref(),
+            mint.key().as_ref(),
+            mpl_token_metadata::state::EDITION.as_bytes()
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub master_edition: Option<UncheckedAccount<'info>>,
+    pub token_metadata_program: Option<Program<'info, Metadata>>,
+    pub rent: Option<Sysvar<'info, Rent>>,
 
     //AB Accounts
     //// AB Config/Signer
+    #[account(
+        seeds=[
+            b"tsab_signer"
+        ],
+        bump,
+    )]
     pub tsab_config: Account<'info, TSABConfig>,
 
     // New Accounts created via CPI
     //// Entity
     /// CHECK: Created via CPI
+    #[account(mut)]
     pub entity: AccountInfo<'info>,
     //// ARCNFT
     /// CHECK: Created via CPI
+    #[account(mut)]
     pub arcnft: AccountInfo<'info>,
+    //// Entity -> ARCNFT Index
+    /// CHECK: Created via CPI
+    #[account(mut)]
+    pub entity_arcnft: AccountInfo<'info>,
     
     // Registry Accounts
     //// Registry Config/Signer
+    #[account(
+        constraint = registry_config.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    //// Registry Program
+    pub registry_program: Program<'info, Registry>,
+    //// AB Registration
+    #[account(
+        constraint = tsab_registration.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub tsab_registration: Account<'info, ActionBundleRegistration>,
+
+    // CoreDS Accounts
+    //// CoreDS Program
+    pub core_ds_program: Program<'info, CoreDs>,
+    //// Noop Program, CoreDS records component changes through it
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+    //// Registry Instance
+    #[account(
+        mut,
+        constraint = registry_instance.version == CORE_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_instance: Account<'info, RegistryInstance>,
+}
+
+#[derive(Accounts)]
+pub struct ImportMetaplex<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    // Only the Metaplex update authority can wrap their NFTs in ARC
+    pub update_authority: Signer<'info>,
+
+    //SPL Mint
+    pub mint: Account<'info, Mint>,
+
+    //Metaplex Accounts
+    #[account(
+        constraint = metaplex_metadata.mint == mint.key(),
+        constraint = metaplex_metadata.update_authority == update_authority.key() @ TSABError::InvalidUpdateAuthority,
+        seeds=[
+            mpl_token_metadata::state::PREFIX.as_bytes(),
+            mpl_token_metadata::ID.as_ref(),
+            mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metaplex_metadata: Box<Account<'info, MetadataAccount>>,
+
+    //AB Accounts
+    //// AB Config/Signer
+    #[account(
+        seeds=[
+            b"tsab_signer"
+        ],
+        bump,
+    )]
+    pub tsab_config: Account<'info, TSABConfig>,
+
+    // New Accounts created via CPI
+    //// Entity
+    /// CHECK: Created via CPI
+    #[account(mut)]
+    pub entity: AccountInfo<'info>,
+    //// ARCNFT
+    /// CHECK: Created via CPI
+    #[account(mut)]
+    pub arcnft: AccountInfo<'info>,
+    //// Entity -> ARCNFT Index
+    /// CHECK: Created via CPI
+    #[account(mut)]
+    pub entity_arcnft: AccountInfo<'info>,
+
+    // Registry Accounts
+    //// Registry Config/Signer
+    #[account(
+        constraint = registry_config.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
     pub registry_config: Account<'info, RegistryConfig>,
     //// Registry Program
     pub registry_program: Program<'info, Registry>,
     //// AB Registration
+    #[account(
+        constraint = tsab_registration.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
     pub tsab_registration: Account<'info, ActionBundleRegistration>,
 
     // CoreDS Accounts
     //// CoreDS Program
     pub core_ds_program: Program<'info, CoreDs>,
+    //// Noop Program, CoreDS records component changes through it
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
     //// Registry Instance
+    #[account(
+        mut,
+        constraint = registry_instance.version == CORE_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
     pub registry_instance: Account<'info, RegistryInstance>,
 }
+
+#[derive(Accounts)]
+pub struct BurnARCNFT<'info> {
+    // Holder burns the token and gets all the rent back
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    //SPL Accounts
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = holder,
+    )]
+    pub holder_ata: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+
+    //AB Accounts
+    //// AB Config/Signer
+    #[account(
+        seeds=[
+            b"tsab_signer"
+        ],
+        bump,
+    )]
+    pub tsab_config: Account<'info, TSABConfig>,
+
+    // Accounts closed via CPI
+    //// Entity
+    #[account(
+        mut,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+    //// ARCNFT
+    /// CHECK: Closed via CPI
+    #[account(mut)]
+    pub arcnft: AccountInfo<'info>,
+    //// Entity -> ARCNFT Index
+    /// CHECK: Closed via CPI
+    #[account(mut)]
+    pub entity_arcnft: AccountInfo<'info>,
+    //// Entity Relations, only checked to be closed when the Entity is
+    /// CHECK: Checked in CoreDS
+    pub entity_relations: AccountInfo<'info>,
+
+    // Registry Accounts
+    //// Registry Config/Signer
+    #[account(
+        constraint = registry_config.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    //// Registry Program
+    pub registry_program: Program<'info, Registry>,
+    //// AB Registration
+    #[account(
+        constraint = tsab_registration.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub tsab_registration: Account<'info, ActionBundleRegistration>,
+
+    // CoreDS Accounts
+    //// CoreDS Program
+    pub core_ds_program: Program<'info, CoreDs>,
+    //// Noop Program, CoreDS records component changes through it
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SyncMetadata<'info> {
+    //AB Accounts
+    //// AB Config/Signer, also the Metaplex update authority
+    #[account(
+        seeds=[
+            b"tsab_signer"
+        ],
+        bump,
+    )]
+    pub tsab_config: Account<'info, TSABConfig>,
+
+    pub mint: Account<'info, Mint>,
+    #[account(
+        has_one = entity,
+        has_one = mint,
+        seeds=[
+            SEEDS_ARCNFT_PREFIX,
+            mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = core_ds::ID,
+        constraint = arcnft.version == CORE_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub arcnft: Account<'info, ARCNFT>,
+    #[account(
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+
+    //Metaplex Accounts
+    /// CHECK: Updated via CPI to Token Metadata
+    #[account(
+        mut,
+        seeds=[
+            mpl_token_metadata::state::PREFIX.as_bytes(),
+            mpl_token_metadata::ID.as_ref(),
+            mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metaplex_metadata: UncheckedAccount<'info>,
+    pub token_metadata_program: Program<'info, Metadata>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMetadata<'info> {
+    // Checked against the update authority stored in the Metadata Component
+    pub update_authority: Signer<'info>,
+
+    //AB Accounts
+    //// AB Config/Signer
+    #[account(
+        seeds=[
+            b"tsab_signer"
+        ],
+        bump,
+    )]
+    pub tsab_config: Account<'info, TSABConfig>,
+
+    // Entity modified via CPI
+    #[account(
+        mut,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+
+    // Registry Accounts
+    //// Registry Config/Signer
+    #[account(
+        constraint = registry_config.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    //// Registry Program
+    pub registry_program: Program<'info, Registry>,
+    //// AB Registration
+    #[account(
+        constraint = tsab_registration.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub tsab_registration: Account<'info, ActionBundleRegistration>,
+
+    // CoreDS Accounts
+    //// CoreDS Program
+    pub core_ds_program: Program<'info, CoreDs>,
+    //// Noop Program, CoreDS records component changes through it
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetComponent<'info> {
+    // Pays for the realloc if the component is new to the Entity
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    // Checked against the update authority stored in the Metadata Component
+    pub update_authority: Signer<'info>,
+
+    //AB Accounts
+    //// AB Config/Signer
+    #[account(
+        seeds=[
+            b"tsab_signer"
+        ],
+        bump,
+    )]
+    pub tsab_config: Account<'info, TSABConfig>,
+
+    // Entity modified via CPI
+    #[account(
+        mut,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+
+    // Registry Accounts
+    //// Registry Config/Signer
+    #[account(
+        constraint = registry_config.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    //// Registry Program
+    pub registry_program: Program<'info, Registry>,
+    //// AB Registration
+    #[account(
+        constraint = tsab_registration.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub tsab_registration: Account<'info, ActionBundleRegistration>,
+
+    // CoreDS Accounts
+    //// CoreDS Program
+    pub core_ds_program: Program<'info, CoreDs>,
+    //// Noop Program, CoreDS records component changes through it
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetCreators<'info> {
+    // Pays for the realloc if the component is new to the Entity
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    // Checked against the update authority stored in the Metadata Component
+    pub update_authority: Signer<'info>,
+
+    //SPL Mint, has to be the one in the Metadata Component
+    pub mint: Account<'info, Mint>,
+
+    //Metaplex Accounts
+    //// Empty if the mint was never mirrored in Metaplex
+    /// CHECK: Deserialized in the instruction if it exists
+    #[account(
+        mut,
+        seeds=[
+            mpl_token_metadata::state::PREFIX.as_bytes(),
+            mpl_token_metadata::ID.as_ref(),
+            mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metaplex_metadata: UncheckedAccount<'info>,
+    pub token_metadata_program: Program<'info, Metadata>,
+
+    //AB Accounts
+    //// AB Config/Signer
+    #[account(
+        seeds=[
+            b"tsab_signer"
+        ],
+        bump,
+    )]
+    pub tsab_config: Account<'info, TSABConfig>,
+
+    // Entity modified via CPI
+    #[account(
+        mut,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+
+    // Registry Accounts
+    //// Registry Config/Signer
+    #[account(
+        constraint = registry_config.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    //// Registry Program
+    pub registry_program: Program<'info, Registry>,
+    //// AB Registration
+    #[account(
+        constraint = tsab_registration.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub tsab_registration: Account<'info, ActionBundleRegistration>,
+
+    // CoreDS Accounts
+    //// CoreDS Program
+    pub core_ds_program: Program<'info, CoreDs>,
+    //// Noop Program, CoreDS records component changes through it
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyCollection<'info> {
+    // Checked against the update authority in the collection's Metadata Component
+    pub collection_authority: Signer<'info>,
+
+    //AB Accounts
+    //// AB Config/Signer
+    #[account(
+        seeds=[
+            b"tsab_signer"
+        ],
+        bump,
+    )]
+    pub tsab_config: Account<'info, TSABConfig>,
+
+    // Entity modified via CPI
+    #[account(
+        mut,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+    // Collections have to live in the same Registry Instance as their items
+    #[account(
+        constraint = collection_entity.registry.key() == entity.registry.key() && collection_entity.instance == entity.instance,
+        constraint = collection_entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub collection_entity: Box<Account<'info, Entity>>,
+
+    // Registry Accounts
+    //// Registry Config/Signer
+    #[account(
+        constraint = registry_config.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    //// Registry Program
+    pub registry_program: Program<'info, Registry>,
+    //// AB Registration
+    #[account(
+        constraint = tsab_registration.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub tsab_registration: Account<'info, ActionBundleRegistration>,
+
+    // CoreDS Accounts
+    //// CoreDS Program
+    pub core_ds_program: Program<'info, CoreDs>,
+    //// Noop Program, CoreDS records component changes through it
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct VerifyCreator<'info> {
+    pub creator: Signer<'info>,
+
+    //SPL Mint, has to be the one in the Metadata Component
+    pub mint: Account<'info, Mint>,
+
+    //Metaplex Accounts
+    //// Empty if the mint was never mirrored in Metaplex
+    /// CHECK: Deserialized in the instruction if it exists
+    #[account(
+        mut,
+        seeds=[
+            mpl_token_metadata::state::PREFIX.as_bytes(),
+            mpl_token_metadata::ID.as_ref(),
+            mint.key().as_ref()
+        ],
+        bump,
+        seeds::program = mpl_token_metadata::ID
+    )]
+    pub metaplex_metadata: UncheckedAccount<'info>,
+    pub token_metadata_program: Program<'info, Metadata>,
+
+    //AB Accounts
+    //// AB Config/Signer
+    #[account(
+        seeds=[
+            b"tsab_signer"
+        ],
+        bump,
+    )]
+    pub tsab_config: Account<'info, TSABConfig>,
+
+    // Entity modified via CPI
+    #[account(
+        mut,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+
+    // Registry Accounts
+    //// Registry Config/Signer
+    #[account(
+        constraint = registry_config.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    //// Registry Program
+    pub registry_program: Program<'info, Registry>,
+    //// AB Registration
+    #[account(
+        constraint = tsab_registration.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub tsab_registration: Account<'info, ActionBundleRegistration>,
+
+    // CoreDS Accounts
+    //// CoreDS Program
+    pub core_ds_program: Program<'info, CoreDs>,
+    //// Noop Program, CoreDS records component changes through it
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(entity_id: u64, metadata: ComponentMetadata, decimals: u8)]
+pub struct CreateFungible<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+
+    //SPL Mint
+    //// TSAB keeps the mint authority so supply can only change through it
+    #[account(
+        init,
+        payer=payer,
+        mint::decimals = decimals,
+        mint::authority = tsab_config,
+    )]
+    pub mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+
+    //AB Accounts
+    //// AB Config/Signer
+    #[account(
+        seeds=[
+            b"tsab_signer"
+        ],
+        bump,
+    )]
+    pub tsab_config: Account<'info, TSABConfig>,
+
+    // New Accounts created via CPI
+    //// Entity
+    /// CHECK: Created via CPI
+    #[account(mut)]
+    pub entity: AccountInfo<'info>,
+
+    // Registry Accounts
+    //// Registry Config/Signer
+    #[account(
+        constraint = registry_config.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    //// Registry Program
+    pub registry_program: Program<'info, Registry>,
+    //// AB Registration
+    #[account(
+        constraint = tsab_registration.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub tsab_registration: Account<'info, ActionBundleRegistration>,
+
+    // CoreDS Accounts
+    //// CoreDS Program
+    pub core_ds_program: Program<'info, CoreDs>,
+    //// Noop Program, CoreDS records component changes through it
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+    //// Registry Instance
+    #[account(
+        mut,
+        constraint = registry_instance.version == CORE_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_instance: Account<'info, RegistryInstance>,
+}
+
+#[derive(Accounts)]
+pub struct MintSupply<'info> {
+    // Checked against the update authority stored in the Metadata Component
+    pub update_authority: Signer<'info>,
+
+    //SPL Accounts
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+
+    //AB Accounts
+    //// AB Config/Signer
+    #[account(
+        seeds=[
+            b"tsab_signer"
+        ],
+        bump,
+    )]
+    pub tsab_config: Account<'info, TSABConfig>,
+
+    // Entity modified via CPI
+    #[account(
+        mut,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+
+    // Registry Accounts
+    //// Registry Config/Signer
+    #[account(
+        constraint = registry_config.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    //// Registry Program
+    pub registry_program: Program<'info, Registry>,
+    //// AB Registration
+    #[account(
+        constraint = tsab_registration.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub tsab_registration: Account<'info, ActionBundleRegistration>,
+
+    // CoreDS Accounts
+    //// CoreDS Program
+    pub core_ds_program: Program<'info, CoreDs>,
+    //// Noop Program, CoreDS records component changes through it
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct BurnSupply<'info> {
+    pub holder: Signer<'info>,
+
+    //SPL Accounts
+    #[account(mut)]
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = holder,
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+
+    //AB Accounts
+    //// AB Config/Signer
+    #[account(
+        seeds=[
+            b"tsab_signer"
+        ],
+        bump,
+    )]
+    pub tsab_config: Account<'info, TSABConfig>,
+
+    // Entity modified via CPI
+    #[account(
+        mut,
+        constraint = entity.version == ENTITY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub entity: Box<Account<'info, Entity>>,
+
+    // Registry Accounts
+    //// Registry Config/Signer
+    #[account(
+        constraint = registry_config.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub registry_config: Account<'info, RegistryConfig>,
+    //// Registry Program
+    pub registry_program: Program<'info, Registry>,
+    //// AB Registration
+    #[account(
+        constraint = tsab_registration.version == REGISTRY_LAYOUT_VERSION @ MigrationError::OutdatedLayout
+    )]
+    pub tsab_registration: Account<'info, ActionBundleRegistration>,
+
+    // CoreDS Accounts
+    //// CoreDS Program
+    pub core_ds_program: Program<'info, CoreDs>,
+    //// Noop Program, CoreDS records component changes through it
+    /// CHECK: Checked in CoreDS
+    pub noop_program: AccountInfo<'info>,
+}
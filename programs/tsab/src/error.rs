@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum TSABError {
+    #[msg("Metadata component belongs to a different mint!")]
+    MetadataMintMismatch,
+    #[msg("Signer isn't the metadata update authority!")]
+    InvalidUpdateAuthority,
+    #[msg("Metadata is frozen!")]
+    MetadataImmutable,
+    #[msg("Metadata name is longer than 32 bytes!")]
+    NameTooLong,
+    #[msg("Metadata symbol is longer than 10 bytes!")]
+    SymbolTooLong,
+    #[msg("Metadata uri is longer than 200 bytes!")]
+    UriTooLong,
+    #[msg("Collection doesn't match the Entity's collection!")]
+    CollectionMismatch,
+    #[msg("NFTs can't have more than 5 creators!")]
+    TooManyCreators,
+    #[msg("Creator shares must add up to 100!")]
+    InvalidCreatorShares,
+    #[msg("Creator is listed more than once!")]
+    DuplicateCreator,
+    #[msg("Signer isn't one of the Entity's creators!")]
+    CreatorNotFound,
+    #[msg("Supply component belongs to a different mint!")]
+    SupplyMintMismatch,
+    #[msg("Minting would exceed the max supply!")]
+    MaxSupplyExceeded,
+    #[msg("Component isn't configured in TSAB!")]
+    ComponentNotConfigured,
+    #[msg("Signer isn't the TSAB authority!")]
+    InvalidAuthority,
+    #[msg("Component Schema account isn't the one registered for this schema!")]
+    InvalidComponentSchema,
+    #[msg("Verified creators can't be removed!")]
+    VerifiedCreatorRemoved,
+    #[msg("Metadata is larger than the Entity's Metadata Component max size!")]
+    MetadataExceedsComponentSize,
+}
@@ -1,7 +1,15 @@
+// Anchor's Error is large by design and every instruction returns it
+#![allow(clippy::result_large_err)]
+// Explicit returns are the house style
+#![allow(clippy::needless_return)]
+
 use anchor_lang::prelude::*;
 use std::collections::BTreeMap;
-use anchor_lang::solana_program::keccak::Hash;
-use core_ds::account::MaxSize;
+use anchor_spl::token::spl_token::instruction::AuthorityType;
+use core_ds::account::{MaxSize, Entity};
+use core_ds::state::SerializedComponent;
+use mpl_token_metadata::state::{DataV2, Metadata as MetaplexMetadata, TokenMetadataAccount};
+use arc_action_bundle::{bundle_context, bundle_signer_seeds, get_component, modify_components, BundleContext};
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
@@ -9,80 +17,740 @@ mod context;
 mod account;
 mod component;
 mod constant;
+mod error;
 
+use account::*;
+use constant::*;
 use context::*;
 use component::*;
+use error::*;
 
 #[program]
 pub mod tsab {
-    use core_ds::state::SerializedComponent;
-
     use crate::component::ComponentMetadata;
 
     use super::*;
 
-    pub fn initalize(ctx:Context<Initialize>, components:BTreeMap<[u8;32], Pubkey>) -> Result<()> {
+    /**
+     * Registers TSAB's Component Schemas with the Registry and keeps track of their PDAs.
+     */
+    pub fn initalize(ctx:Context<Initialize>) -> Result<()> {
         ctx.accounts.tsab_config.authority = ctx.accounts.payer.key();
-        ctx.accounts.tsab_config.components = components;        
+
+        let schemas = [
+            (COMPONENT_METADATA, SCHEMA_METADATA, ctx.accounts.metadata_component.to_account_info()),
+            (COMPONENT_COLLECTION, SCHEMA_COLLECTION, ctx.accounts.collection_component.to_account_info()),
+            (COMPONENT_CREATORS, SCHEMA_CREATORS, ctx.accounts.creators_component.to_account_info()),
+            (COMPONENT_SUPPLY, SCHEMA_SUPPLY, ctx.accounts.supply_component.to_account_info()),
+        ];
+
+        for (name, schema, component) in schemas {
+            let address = Pubkey::find_program_address(&[schema.as_bytes()], &registry::ID).0;
+            require_keys_eq!(component.key(), address, TSABError::InvalidComponentSchema);
+            ctx.accounts.tsab_config.components.insert(get_component_hash(name), component.key());
+
+            // register_component is open to anyone and seeded only by the schema, so it may already exist
+            if *component.owner == registry::ID {
+                continue;
+            }
+            let register_component_ctx = CpiContext::new(
+                ctx.accounts.registry_program.to_account_info(),
+                registry::cpi::accounts::RegisterComponent{
+                    payer: ctx.accounts.payer.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    component: component.clone(),
+                    registry_config: ctx.accounts.registry_config.to_account_info(),
+                }
+            );
+            registry::cpi::register_component(register_component_ctx, schema.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Points a component name at a Component Schema, or repoints it if it's already mapped.
+     */
+    pub fn add_component_mapping(ctx:Context<AddComponentMapping>, name: String, component: Pubkey) -> Result<()> {
+        ctx.accounts.tsab_config.components.insert(get_component_hash(&name), component);
+        Ok(())
+    }
+
+    pub fn remove_component_mapping(ctx:Context<RemoveComponentMapping>, name: String) -> Result<()> {
+        if ctx.accounts.tsab_config.components.remove(&get_component_hash(&name)).is_none() {
+            return err!(TSABError::ComponentNotConfigured);
+        }
+        Ok(())
+    }
+
+    pub fn set_authority(ctx:Context<SetAuthority>, new_authority: Pubkey) -> Result<()> {
+        ctx.accounts.tsab_config.authority = new_authority;
         Ok(())
     }
 
     pub fn mint_metadata(ctx:Context<MintMetdata>, entity_id: u64, metadata:ComponentMetadata) -> Result<()> {
+        metadata.validate()?;
+        // sync_metadata only syncs the mint named in the Metadata component
+        require_keys_eq!(metadata.mint, ctx.accounts.mint.key(), TSABError::MetadataMintMismatch);
+
         // Create Entity
-        let system_signer_seeds:&[&[u8]] = &[
-            b"tsab_signer",
-            &[*ctx.bumps.get("tsab_config").unwrap()]
-        ];
-        let signer_seeds = &[system_signer_seeds];
+        let bundle = bundle_context!(ctx, tsab_config, tsab_registration, b"tsab_signer");
+
+        let metadata_component = ctx.accounts.tsab_config.get_component(COMPONENT_METADATA)?;
+        let mut components = BTreeMap::new();
+        components.insert(
+            metadata_component,
+            SerializedComponent {
+                max_size: ComponentMetadata::get_max_size(),
+                data: metadata.try_to_vec().unwrap()
+            }
+        );
+
+        bundle.create_entity(
+            ctx.accounts.registry_instance.to_account_info(),
+            ctx.accounts.entity.to_account_info(),
+            entity_id,
+            components
+        )?;
+
+        // Mint the single SPL Token to the Recipient
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.recipient_ata.to_account_info(),
+                    authority: ctx.accounts.tsab_config.to_account_info(),
+                },
+                bundle_signer_seeds!(bundle.seed, bundle.bump)
+            ),
+            1
+        )?;
+
+        // Create ARCNFT
+        bundle.mint_nft(
+            ctx.accounts.registry_instance.to_account_info(),
+            ctx.accounts.entity.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.arcnft.to_account_info(),
+            ctx.accounts.entity_arcnft.to_account_info()
+        )?;
+
+        match (
+            ctx.accounts.metaplex_metadata.as_ref(),
+            ctx.accounts.master_edition.as_ref(),
+            ctx.accounts.token_metadata_program.as_ref(),
+            ctx.accounts.rent.as_ref()
+        ) {
+            (Some(metaplex_metadata), Some(master_edition), Some(token_metadata_program), Some(rent)) => {
+                // Mirror the Metadata Component in Metaplex so wallets can display it. TSAB stays the update authority so it can sync changes later.
+                anchor_spl::metadata::create_metadata_accounts_v3(
+                    CpiContext::new_with_signer(
+                        token_metadata_program.to_account_info(),
+                        anchor_spl::metadata::CreateMetadataAccountsV3 {
+                            metadata: metaplex_metadata.to_account_info(),
+                            mint: ctx.accounts.mint.to_account_info(),
+                            mint_authority: ctx.accounts.tsab_config.to_account_info(),
+                            payer: ctx.accounts.payer.to_account_info(),
+                            update_authority: ctx.accounts.tsab_config.to_account_info(),
+                            system_program: ctx.accounts.system_program.to_account_info(),
+                            rent: rent.to_account_info(),
+                        },
+                        bundle_signer_seeds!(bundle.seed, bundle.bump)
+                    ),
+                    get_metaplex_data(&metadata, None, None),
+                    metadata.is_mutable,
+                    true,
+                    None
+                )?;
+
+                // Master Edition takes over the Mint Authority, which keeps supply fixed at 1
+                anchor_spl::metadata::create_master_edition_v3(
+                    CpiContext::new_with_signer(
+                        token_metadata_program.to_account_info(),
+                        anchor_spl::metadata::CreateMasterEditionV3 {
+                            edition: master_edition.to_account_info(),
+                            mint: ctx.accounts.mint.to_account_info(),
+                            update_authority: ctx.accounts.tsab_config.to_account_info(),
+                            mint_authority: ctx.accounts.tsab_config.to_account_info(),
+                            payer: ctx.accounts.payer.to_account_info(),
+                            metadata: metaplex_metadata.to_account_info(),
+                            token_program: ctx.accounts.token_program.to_account_info(),
+                            system_program: ctx.accounts.system_program.to_account_info(),
+                            rent: rent.to_account_info(),
+                        },
+                        bundle_signer_seeds!(bundle.seed, bundle.bump)
+                    ),
+                    Some(0)
+                )?;
+            },
+            _ => {
+                // Revoke Mint Authority so supply is fixed at 1
+                anchor_spl::token::set_authority(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        anchor_spl::token::SetAuthority {
+                            current_authority: ctx.accounts.tsab_config.to_account_info(),
+                            account_or_mint: ctx.accounts.mint.to_account_info(),
+                        },
+                        bundle_signer_seeds!(bundle.seed, bundle.bump)
+                    ),
+                    AuthorityType::MintTokens,
+                    None
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Pushes the Metadata and Creators Components of an ARCNFT to its Metaplex Metadata account.
+     * The component is the source of truth, so anyone can crank this.
+     */
+    pub fn sync_metadata(ctx:Context<SyncMetadata>) -> Result<()> {
+        let metadata_component = ctx.accounts.tsab_config.get_component(COMPONENT_METADATA)?;
+        let metadata:ComponentMetadata = get_component(&ctx.accounts.entity, &metadata_component)?;
+
+        if metadata.mint != ctx.accounts.mint.key() {
+            return err!(TSABError::MetadataMintMismatch);
+        }
+
+        let creators_component = ctx.accounts.tsab_config.get_component(COMPONENT_CREATORS)?;
+        let creators:Option<ComponentCreators> = match ctx.accounts.entity.components.contains_key(&creators_component) {
+            true => Some(get_component(&ctx.accounts.entity, &creators_component)?),
+            false => None,
+        };
+        let existing = MetaplexMetadata::from_account_info(&ctx.accounts.metaplex_metadata.to_account_info())?;
+
+        anchor_spl::metadata::update_metadata_accounts_v2(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                anchor_spl::metadata::UpdateMetadataAccountsV2 {
+                    metadata: ctx.accounts.metaplex_metadata.to_account_info(),
+                    update_authority: ctx.accounts.tsab_config.to_account_info(),
+                },
+                bundle_signer_seeds!(b"tsab_signer", *ctx.bumps.get("tsab_config").unwrap())
+            ),
+            None,
+            Some(get_metaplex_data(&metadata, creators.as_ref(), Some(&existing))),
+            None,
+            Some(metadata.is_mutable)
+        )?;
+
+        Ok(())
+    }
+
+    /**
+     * Wraps an existing Metaplex NFT in a new Entity, with the Metadata Component copied from Metaplex.
+     */
+    pub fn import_metaplex(ctx:Context<ImportMetaplex>, entity_id: u64) -> Result<()> {
+        // Create Entity
+        let bundle = bundle_context!(ctx, tsab_config, tsab_registration, b"tsab_signer");
+
+        let metadata = get_component_metadata(&ctx.accounts.metaplex_metadata);
+        metadata.validate()?;
+        let metadata_component = ctx.accounts.tsab_config.get_component(COMPONENT_METADATA)?;
+        let mut components = BTreeMap::new();
+        components.insert(
+            metadata_component,
+            SerializedComponent {
+                max_size: ComponentMetadata::get_max_size(),
+                data: metadata.try_to_vec().unwrap()
+            }
+        );
+
+        bundle.create_entity(
+            ctx.accounts.registry_instance.to_account_info(),
+            ctx.accounts.entity.to_account_info(),
+            entity_id,
+            components
+        )?;
+
+        // Create ARCNFT
+        bundle.mint_nft(
+            ctx.accounts.registry_instance.to_account_info(),
+            ctx.accounts.entity.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.arcnft.to_account_info(),
+            ctx.accounts.entity_arcnft.to_account_info()
+        )?;
+
+        Ok(())
+    }
+
+    /**
+     * Changes any of name, symbol or uri. Only the update authority can do this, and only while the metadata is mutable.
+     */
+    pub fn update_metadata(ctx:Context<UpdateMetadata>, name: Option<String>, symbol: Option<String>, uri: Option<String>) -> Result<()> {
+        let mut metadata = get_metadata(&ctx.accounts.tsab_config, &ctx.accounts.entity, &ctx.accounts.update_authority.key())?;
+        if !metadata.is_mutable {
+            return err!(TSABError::MetadataImmutable);
+        }
+
+        if let Some(name) = name {
+            metadata.name = name;
+        }
+        if let Some(symbol) = symbol {
+            metadata.symbol = symbol;
+        }
+        if let Some(uri) = uri {
+            metadata.uri = uri;
+        }
+        metadata.validate()?;
+
+        set_metadata(&ctx, &metadata)
+    }
+
+    /**
+     * Hands the metadata over to a new update authority. Like Metaplex, this is allowed on frozen metadata.
+     */
+    pub fn set_update_authority(ctx:Context<UpdateMetadata>, new_update_authority: Pubkey) -> Result<()> {
+        let mut metadata = get_metadata(&ctx.accounts.tsab_config, &ctx.accounts.entity, &ctx.accounts.update_authority.key())?;
+        metadata.update_authority = new_update_authority;
+        set_metadata(&ctx, &metadata)
+    }
+
+    /**
+     * Permanently marks the metadata as immutable.
+     */
+    pub fn freeze_metadata(ctx:Context<UpdateMetadata>) -> Result<()> {
+        let mut metadata = get_metadata(&ctx.accounts.tsab_config, &ctx.accounts.entity, &ctx.accounts.update_authority.key())?;
+        if !metadata.is_mutable {
+            return err!(TSABError::MetadataImmutable);
+        }
+
+        metadata.is_mutable = false;
+        set_metadata(&ctx, &metadata)
+    }
+
+    /**
+     * Puts the Entity in a collection. Starts out unverified until the collection's update authority signs off on it.
+     */
+    pub fn set_collection(ctx:Context<SetComponent>, collection: Pubkey) -> Result<()> {
+        let metadata = get_metadata(&ctx.accounts.tsab_config, &ctx.accounts.entity, &ctx.accounts.update_authority.key())?;
+        if !metadata.is_mutable {
+            return err!(TSABError::MetadataImmutable);
+        }
+
+        let collection_component = ctx.accounts.tsab_config.get_component(COMPONENT_COLLECTION)?;
+        let component = ComponentCollection {
+            collection,
+            verified: false
+        };
+
+        let bundle = bundle_context!(ctx, tsab_config, tsab_registration, b"tsab_signer");
+        set_component(&bundle, &ctx.accounts.entity, collection_component, ComponentCollection::get_max_size(), component.try_to_vec().unwrap())
+    }
+
+    /**
+     * Verifies the Entity belongs to the collection. Signed by the update authority of the collection Entity's metadata.
+     */
+    pub fn verify_collection(ctx:Context<VerifyCollection>) -> Result<()> {
+        get_metadata(&ctx.accounts.tsab_config, &ctx.accounts.collection_entity, &ctx.accounts.collection_authority.key())?;
+
+        let collection_component = ctx.accounts.tsab_config.get_component(COMPONENT_COLLECTION)?;
+        let mut component:ComponentCollection = get_component(&ctx.accounts.entity, &collection_component)?;
+        if component.collection != ctx.accounts.collection_entity.key() {
+            return err!(TSABError::CollectionMismatch);
+        }
+        component.verified = true;
 
-        let init_entity_ctx = CpiContext::new_with_signer(
+        modify_components(
             ctx.accounts.registry_program.to_account_info(),
-            registry::cpi::accounts::InitEntity{
-                payer: ctx.accounts.payer.to_account_info(),
-                system_program: ctx.accounts.system_program.to_account_info(),
+            registry::cpi::accounts::ModifyComponent{
                 registry_config: ctx.accounts.registry_config.to_account_info(),
                 entity: ctx.accounts.entity.to_account_info(),
-                registry_instance: ctx.accounts.registry_instance.to_account_info(),
                 action_bundle: ctx.accounts.tsab_config.to_account_info(),
                 action_bundle_registration: ctx.accounts.tsab_registration.to_account_info(),
                 core_ds: ctx.accounts.core_ds_program.to_account_info(),
+                noop_program: ctx.accounts.noop_program.to_account_info(),
+            },
+            b"tsab_signer",
+            *ctx.bumps.get("tsab_config").unwrap(),
+            vec![(collection_component, component.try_to_vec().unwrap())]
+        )
+    }
+
+    /**
+     * Sets the royalty split for the Entity. New creators start unverified, unless they're the signing update authority.
+     * Creators who already verified stay verified and can't be removed.
+     * If TSAB mirrors the Entity in Metaplex, the creators are pushed there as well, with the update authority's own verification.
+     */
+    pub fn set_creators(ctx:Context<SetCreators>, creators: Vec<(Pubkey, u8)>) -> Result<()> {
+        let metadata = get_metadata(&ctx.accounts.tsab_config, &ctx.accounts.entity, &ctx.accounts.update_authority.key())?;
+        if !metadata.is_mutable {
+            return err!(TSABError::MetadataImmutable);
+        }
+        require_keys_eq!(metadata.mint, ctx.accounts.mint.key(), TSABError::MetadataMintMismatch);
+
+        let creators_component = ctx.accounts.tsab_config.get_component(COMPONENT_CREATORS)?;
+        let current:Option<ComponentCreators> = match ctx.accounts.entity.components.contains_key(&creators_component) {
+            true => Some(get_component(&ctx.accounts.entity, &creators_component)?),
+            false => None,
+        };
+        let existing = get_metaplex_metadata(&ctx.accounts.metaplex_metadata)?;
+        let verified = get_verified_creators(current.as_ref(), existing.as_ref());
+        let component = ComponentCreators::from_shares(&creators, &verified, &ctx.accounts.update_authority.key())?;
+
+        let bundle = bundle_context!(ctx, tsab_config, tsab_registration, b"tsab_signer");
+        set_component(&bundle, &ctx.accounts.entity, creators_component, ComponentCreators::get_max_size(), component.try_to_vec().unwrap())?;
+
+        match existing {
+            Some(existing) if existing.update_authority == ctx.accounts.tsab_config.key() => mirror_creators(
+                &ctx.accounts.tsab_config.to_account_info(),
+                *ctx.bumps.get("tsab_config").unwrap(),
+                &ctx.accounts.metaplex_metadata,
+                &ctx.accounts.token_metadata_program,
+                get_metaplex_data(&metadata, Some(&component), Some(&existing)),
+                metadata.is_mutable,
+                &ctx.accounts.update_authority
+            ),
+            _ => Ok(()),
+        }
+    }
+
+    /**
+     * Lets a creator sign off on being listed on the Entity.
+     * The creator is verified on the Metaplex mirror too, after the Creators Component is pushed there if TSAB can update it.
+     */
+    pub fn verify_creator(ctx:Context<VerifyCreator>) -> Result<()> {
+        let metadata_component = ctx.accounts.tsab_config.get_component(COMPONENT_METADATA)?;
+        let metadata:ComponentMetadata = get_component(&ctx.accounts.entity, &metadata_component)?;
+        require_keys_eq!(metadata.mint, ctx.accounts.mint.key(), TSABError::MetadataMintMismatch);
+
+        let creators_component = ctx.accounts.tsab_config.get_component(COMPONENT_CREATORS)?;
+        let mut component:ComponentCreators = get_component(&ctx.accounts.entity, &creators_component)?;
+        match component.creators.iter_mut().find(|creator| creator.address == ctx.accounts.creator.key()) {
+            Some(creator) => creator.verified = true,
+            None => return err!(TSABError::CreatorNotFound),
+        }
 
+        modify_components(
+            ctx.accounts.registry_program.to_account_info(),
+            registry::cpi::accounts::ModifyComponent{
+                registry_config: ctx.accounts.registry_config.to_account_info(),
+                entity: ctx.accounts.entity.to_account_info(),
+                action_bundle: ctx.accounts.tsab_config.to_account_info(),
+                action_bundle_registration: ctx.accounts.tsab_registration.to_account_info(),
+                core_ds: ctx.accounts.core_ds_program.to_account_info(),
+                noop_program: ctx.accounts.noop_program.to_account_info(),
             },
-            signer_seeds
-        );
+            b"tsab_signer",
+            *ctx.bumps.get("tsab_config").unwrap(),
+            vec![(creators_component, component.try_to_vec().unwrap())]
+        )?;
 
-        let metadata_component_hash:[u8; 32] = Hash::new(b"metadata").to_bytes();
+        let existing = match get_metaplex_metadata(&ctx.accounts.metaplex_metadata)? {
+            Some(existing) => existing,
+            None => return Ok(()),
+        };
+        let creator = ctx.accounts.creator.to_account_info();
+        if existing.update_authority == ctx.accounts.tsab_config.key() && metadata.is_mutable {
+            return mirror_creators(
+                &ctx.accounts.tsab_config.to_account_info(),
+                *ctx.bumps.get("tsab_config").unwrap(),
+                &ctx.accounts.metaplex_metadata,
+                &ctx.accounts.token_metadata_program,
+                get_metaplex_data(&metadata, Some(&component), Some(&existing)),
+                metadata.is_mutable,
+                &creator
+            );
+        }
+        // Metaplex Metadata TSAB can't update only gets the verification if it already lists the creator
+        if existing.data.creators.iter().flatten().any(|listed| listed.address == creator.key()) {
+            return sign_metaplex_creator(&ctx.accounts.metaplex_metadata, &ctx.accounts.token_metadata_program, &creator);
+        }
+        Ok(())
+    }
+
+    /**
+     * Burns the NFT held by the signer and closes its ARCNFT.
+     * If close_entity is set, all of the Entity's components are stripped and the Entity is closed too.
+     * All rent goes back to the holder.
+     */
+    pub fn burn_arcnft(ctx:Context<BurnARCNFT>, close_entity: bool) -> Result<()> {
+        // Burn the SPL Token and close the holder's token account
+        anchor_spl::token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Burn {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.holder_ata.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                }
+            ),
+            1
+        )?;
+
+        anchor_spl::token::close_account(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::CloseAccount {
+                    account: ctx.accounts.holder_ata.to_account_info(),
+                    destination: ctx.accounts.holder.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                }
+            )
+        )?;
+
+        // Close ARCNFT, the holder gets the rent back
+        let bundle = bundle_context!(ctx, tsab_config, tsab_registration, b"tsab_signer", holder);
+        bundle.burn_nft(
+            ctx.accounts.entity.to_account_info(),
+            ctx.accounts.mint.to_account_info(),
+            ctx.accounts.arcnft.to_account_info(),
+            ctx.accounts.entity_arcnft.to_account_info()
+        )?;
+
+        if !close_entity {
+            return Ok(());
+        }
+
+        // Strip Components
+        let components:Vec<Pubkey> = ctx.accounts.entity.components.keys().cloned().collect();
+        if !components.is_empty() {
+            bundle.remove(ctx.accounts.entity.to_account_info(), components)?;
+        }
+
+        // Close Entity
+        bundle.remove_entity(
+            ctx.accounts.entity.to_account_info(),
+            ctx.accounts.entity_arcnft.to_account_info(),
+            ctx.accounts.entity_relations.to_account_info()
+        )?;
+
+        Ok(())
+    }
+
+    /**
+     * Creates an Entity backed by a fungible or semi-fungible SPL Mint (editions, game currencies).
+     * TSAB holds the mint authority so the Supply Component always matches the Mint.
+     */
+    pub fn create_fungible(ctx:Context<CreateFungible>, entity_id: u64, metadata: ComponentMetadata, decimals: u8, max_supply: Option<u64>) -> Result<()> {
+        metadata.validate()?;
+        if metadata.mint != ctx.accounts.mint.key() {
+            return err!(TSABError::MetadataMintMismatch);
+        }
+
+        // Create Entity
+        let bundle = bundle_context!(ctx, tsab_config, tsab_registration, b"tsab_signer");
+
+        let metadata_component = ctx.accounts.tsab_config.get_component(COMPONENT_METADATA)?;
+        let supply_component = ctx.accounts.tsab_config.get_component(COMPONENT_SUPPLY)?;
+        let supply = ComponentSupply {
+            mint: ctx.accounts.mint.key(),
+            decimals,
+            supply: 0,
+            max_supply,
+        };
         let mut components = BTreeMap::new();
         components.insert(
-            ctx.accounts.tsab_config.components.get(&metadata_component_hash).unwrap().key(),
+            metadata_component,
             SerializedComponent {
                 max_size: ComponentMetadata::get_max_size(),
                 data: metadata.try_to_vec().unwrap()
             }
         );
+        components.insert(
+            supply_component,
+            SerializedComponent {
+                max_size: ComponentSupply::get_max_size(),
+                data: supply.try_to_vec().unwrap()
+            }
+        );
 
-        registry::cpi::init_entity(init_entity_ctx, entity_id, components)?;
+        bundle.create_entity(
+            ctx.accounts.registry_instance.to_account_info(),
+            ctx.accounts.entity.to_account_info(),
+            entity_id,
+            components
+        )?;
 
-        // Create ARCNFT
-        let mint_arcnft_ctx = CpiContext::new_with_signer(
+        Ok(())
+    }
+
+    /**
+     * Mints more of a fungible Entity's token. Only the metadata update authority can issue supply.
+     */
+    pub fn mint_supply(ctx:Context<MintSupply>, amount: u64) -> Result<()> {
+        get_metadata(&ctx.accounts.tsab_config, &ctx.accounts.entity, &ctx.accounts.update_authority.key())?;
+
+        let supply_component = ctx.accounts.tsab_config.get_component(COMPONENT_SUPPLY)?;
+        let mut supply:ComponentSupply = get_component(&ctx.accounts.entity, &supply_component)?;
+        if supply.mint != ctx.accounts.mint.key() {
+            return err!(TSABError::SupplyMintMismatch);
+        }
+        if let Some(max_supply) = supply.max_supply {
+            // Overflowing u64 is past any max supply too
+            let new_supply = ctx.accounts.mint.supply.checked_add(amount).ok_or(TSABError::MaxSupplyExceeded)?;
+            if new_supply > max_supply {
+                return err!(TSABError::MaxSupplyExceeded);
+            }
+        }
+
+        anchor_spl::token::mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::MintTo {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: ctx.accounts.recipient_token_account.to_account_info(),
+                    authority: ctx.accounts.tsab_config.to_account_info(),
+                },
+                bundle_signer_seeds!(b"tsab_signer", *ctx.bumps.get("tsab_config").unwrap())
+            ),
+            amount
+        )?;
+
+        // Mirror the new Mint supply on the Entity
+        ctx.accounts.mint.reload()?;
+        supply.supply = ctx.accounts.mint.supply;
+
+        modify_components(
             ctx.accounts.registry_program.to_account_info(),
-            registry::cpi::accounts::MintARCNFT{
-                payer: ctx.accounts.payer.to_account_info(),
-                system_program: ctx.accounts.system_program.to_account_info(),
+            registry::cpi::accounts::ModifyComponent{
                 registry_config: ctx.accounts.registry_config.to_account_info(),
                 entity: ctx.accounts.entity.to_account_info(),
-                registry_instance: ctx.accounts.registry_instance.to_account_info(),
                 action_bundle: ctx.accounts.tsab_config.to_account_info(),
                 action_bundle_registration: ctx.accounts.tsab_registration.to_account_info(),
                 core_ds: ctx.accounts.core_ds_program.to_account_info(),
-                mint: ctx.accounts.mint.to_account_info(),
-                arcnft: ctx.accounts.arcnft.to_account_info(),
+                noop_program: ctx.accounts.noop_program.to_account_info(),
             },
-            signer_seeds
-        );
+            b"tsab_signer",
+            *ctx.bumps.get("tsab_config").unwrap(),
+            vec![(supply_component, supply.try_to_vec().unwrap())]
+        )
+    }
 
-        registry::cpi::mint_arcnft(mint_arcnft_ctx)?;
+    /**
+     * Burns tokens of a fungible Entity from the signer's token account.
+     */
+    pub fn burn_supply(ctx:Context<BurnSupply>, amount: u64) -> Result<()> {
+        let supply_component = ctx.accounts.tsab_config.get_component(COMPONENT_SUPPLY)?;
+        let mut supply:ComponentSupply = get_component(&ctx.accounts.entity, &supply_component)?;
+        if supply.mint != ctx.accounts.mint.key() {
+            return err!(TSABError::SupplyMintMismatch);
+        }
 
-        Ok(())
+        anchor_spl::token::burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                anchor_spl::token::Burn {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    from: ctx.accounts.holder_token_account.to_account_info(),
+                    authority: ctx.accounts.holder.to_account_info(),
+                }
+            ),
+            amount
+        )?;
+
+        // Mirror the new Mint supply on the Entity
+        ctx.accounts.mint.reload()?;
+        supply.supply = ctx.accounts.mint.supply;
+
+        modify_components(
+            ctx.accounts.registry_program.to_account_info(),
+            registry::cpi::accounts::ModifyComponent{
+                registry_config: ctx.accounts.registry_config.to_account_info(),
+                entity: ctx.accounts.entity.to_account_info(),
+                action_bundle: ctx.accounts.tsab_config.to_account_info(),
+                action_bundle_registration: ctx.accounts.tsab_registration.to_account_info(),
+                core_ds: ctx.accounts.core_ds_program.to_account_info(),
+                noop_program: ctx.accounts.noop_program.to_account_info(),
+            },
+            b"tsab_signer",
+            *ctx.bumps.get("tsab_config").unwrap(),
+            vec![(supply_component, supply.try_to_vec().unwrap())]
+        )
+    }
+}
+
+/************************************************ Utility Functions */
+
+// Reads the Metadata Component off the Entity and checks the signer is its update authority
+fn get_metadata(tsab_config: &TSABConfig, entity: &Entity, update_authority: &Pubkey) -> Result<ComponentMetadata> {
+    let metadata_component = tsab_config.get_component(COMPONENT_METADATA)?;
+    let metadata:ComponentMetadata = get_component(entity, &metadata_component)?;
+
+    if metadata.update_authority != *update_authority {
+        return err!(TSABError::InvalidUpdateAuthority);
     }
-}
\ No newline at end of file
+
+    Ok(metadata)
+}
+
+// Writes the Metadata Component back to the Entity
+fn set_metadata(ctx:&Context<UpdateMetadata>, metadata:&ComponentMetadata) -> Result<()> {
+    let metadata_component = ctx.accounts.tsab_config.get_component(COMPONENT_METADATA)?;
+    let data = metadata.serialize_within(ctx.accounts.entity.components.get(&metadata_component).unwrap().max_size)?;
+
+    modify_components(
+        ctx.accounts.registry_program.to_account_info(),
+        registry::cpi::accounts::ModifyComponent{
+            registry_config: ctx.accounts.registry_config.to_account_info(),
+            entity: ctx.accounts.entity.to_account_info(),
+            action_bundle: ctx.accounts.tsab_config.to_account_info(),
+            action_bundle_registration: ctx.accounts.tsab_registration.to_account_info(),
+            core_ds: ctx.accounts.core_ds_program.to_account_info(),
+            noop_program: ctx.accounts.noop_program.to_account_info(),
+        },
+        b"tsab_signer",
+        *ctx.bumps.get("tsab_config").unwrap(),
+        vec![(metadata_component, data)]
+    )
+}
+
+// Adds the component to the Entity, or overwrites it if it's already there
+fn set_component<'info>(bundle:&BundleContext<'_, 'info>, entity:&Account<'info, Entity>, component:Pubkey, max_size:u64, data:Vec<u8>) -> Result<()> {
+    if entity.components.contains_key(&component) {
+        return bundle.modify(entity.to_account_info(), vec![(component, data)]);
+    }
+
+    bundle.add(entity.to_account_info(), vec![(component, SerializedComponent {
+        max_size,
+        data
+    })])
+}
+
+// Metaplex Metadata mirroring the Entity, None if the mint was never mirrored
+fn get_metaplex_metadata(metaplex_metadata:&AccountInfo) -> Result<Option<MetaplexMetadata>> {
+    if metaplex_metadata.data_is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(MetaplexMetadata::from_account_info(metaplex_metadata)?))
+}
+
+// Pushes the Metaplex view of the Entity, then verifies the signing creator there if it lists them. TSAB has to be the Metaplex update authority
+fn mirror_creators<'info>(tsab_config:&AccountInfo<'info>, bump:u8, metaplex_metadata:&AccountInfo<'info>, token_metadata_program:&AccountInfo<'info>, data:DataV2, is_mutable:bool, signer:&AccountInfo<'info>) -> Result<()> {
+    let listed = data.creators.iter().flatten().any(|creator| creator.address == signer.key());
+    anchor_spl::metadata::update_metadata_accounts_v2(
+        CpiContext::new_with_signer(
+            token_metadata_program.clone(),
+            anchor_spl::metadata::UpdateMetadataAccountsV2 {
+                metadata: metaplex_metadata.clone(),
+                update_authority: tsab_config.clone(),
+            },
+            bundle_signer_seeds!(b"tsab_signer", bump)
+        ),
+        None,
+        Some(data),
+        None,
+        Some(is_mutable)
+    )?;
+
+    if listed {
+        return sign_metaplex_creator(metaplex_metadata, token_metadata_program, signer);
+    }
+    Ok(())
+}
+
+// Metaplex only takes a creator's verification with their own signature
+fn sign_metaplex_creator<'info>(metaplex_metadata:&AccountInfo<'info>, token_metadata_program:&AccountInfo<'info>, creator:&AccountInfo<'info>) -> Result<()> {
+    anchor_spl::metadata::sign_metadata(CpiContext::new(
+        token_metadata_program.clone(),
+        anchor_spl::metadata::SignMetadata {
+            creator: creator.clone(),
+            metadata: metaplex_metadata.clone(),
+        }
+    ))
+}
@@ -1,16 +1,623 @@
 import * as anchor from "@project-serum/anchor";
-import { Program } from "@project-serum/anchor";
-import { SolArc } from "../target/types/sol_arc";
+import {
+  ComputeBudgetProgram,
+  Keypair,
+  PublicKey,
+  SYSVAR_RENT_PUBKEY,
+  SystemProgram,
+  Transaction,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import {
+  getConcurrentMerkleTreeAccountSize,
+  SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
+  SPL_NOOP_PROGRAM_ID,
+} from "@solana/spl-account-compression";
+import { keccak_256 } from "js-sha3";
+import { assert } from "chai";
 
-describe("sol-arc", () => {
-  // Configure the client to use the local cluster.
-  anchor.setProvider(anchor.AnchorProvider.env());
+const CORE_DS = new PublicKey("GN5Ww5qa8ej4evFCJxMhV6AFEPKhD1Drdu8qYYptVgDJ");
+const REGISTRY = new PublicKey("H5mieGWWK6qukHoNzbR6ysLxReeQC4JHZcNM6JkPQnm3");
+const TSAB = new PublicKey("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+const TOKEN_METADATA = new PublicKey("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+const TOKEN_PROGRAM = new PublicKey("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+const ASSOCIATED_TOKEN_PROGRAM = new PublicKey("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
 
-  const program = anchor.workspace.SolArc as Program<SolArc>;
+const MAX_DEPTH = 3;
+const MAX_BUFFER_SIZE = 8;
 
-  it("Is initialized!", async () => {
-    // Add your test here.
-    const tx = await program.methods.initialize().rpc();
-    console.log("Your transaction signature", tx);
+// The Registry's compressed instructions take BTreeMaps, which the IDL coder can't encode, so they're Borsh encoded by hand
+const u32 = (n: number) => {
+  const buf = Buffer.alloc(4);
+  buf.writeUInt32LE(n);
+  return buf;
+};
+const u64 = (n: number | anchor.BN) =>
+  new anchor.BN(n).toArrayLike(Buffer, "le", 8);
+const u64be = (n: number) => new anchor.BN(n).toArrayLike(Buffer, "be", 8);
+const bytes = (data: Buffer) => Buffer.concat([u32(data.length), data]);
+const str = (s: string) => bytes(Buffer.from(s));
+const vec = (items: Buffer[]) => Buffer.concat([u32(items.length), ...items]);
+
+type Component = { key: PublicKey; maxSize: number; data: Buffer };
+type Entity = {
+  entityId: number;
+  instance: number;
+  registry: PublicKey;
+  components: Component[];
+};
+
+// BTreeMap<Pubkey, SerializedComponent>, in key order like Borsh writes it
+const components = (comps: Component[]) =>
+  vec(
+    [...comps]
+      .sort((a, b) => Buffer.compare(a.key.toBuffer(), b.key.toBuffer()))
+      .map((c) => Buffer.concat([c.key.toBuffer(), u64(c.maxSize), bytes(c.data)]))
+  );
+const compressedEntity = (e: Entity) =>
+  Buffer.concat([
+    u64(e.entityId),
+    u64(e.instance),
+    e.registry.toBuffer(),
+    components(e.components),
+  ]);
+const modifyOp = (key: PublicKey, data: Buffer) =>
+  Buffer.concat([Buffer.from([1]), key.toBuffer(), bytes(data)]);
+
+const keccak = (...data: Buffer[]) =>
+  Buffer.from(keccak_256.arrayBuffer(Buffer.concat(data)));
+const EMPTY = Buffer.alloc(32);
+const emptyNode = (depth: number): Buffer =>
+  depth === 0 ? EMPTY : keccak(emptyNode(depth - 1), emptyNode(depth - 1));
+
+// Same as arc-sdk's CompressedIndex::get_proof
+function getProof(leaves: Buffer[], index: number) {
+  let level = [...leaves];
+  let position = index;
+  const proof: Buffer[] = [];
+  for (let depth = 0; depth < MAX_DEPTH; depth++) {
+    const empty = emptyNode(depth);
+    proof.push(level[position ^ 1] ?? empty);
+    const next: Buffer[] = [];
+    for (let i = 0; i < level.length; i += 2) {
+      next.push(keccak(level[i], level[i + 1] ?? empty));
+    }
+    level = next;
+    position >>= 1;
+  }
+  return { root: level[0] ?? emptyNode(MAX_DEPTH), proof };
+}
+
+const discriminator = (name: string) =>
+  Buffer.from(anchor.utils.sha256.hash(`global:${name}`), "hex").subarray(0, 8);
+const readonly = (pubkey: PublicKey, isSigner = false) => ({
+  pubkey,
+  isSigner,
+  isWritable: false,
+});
+const writable = (pubkey: PublicKey, isSigner = false) => ({
+  pubkey,
+  isSigner,
+  isWritable: true,
+});
+const pda = (seeds: Buffer[], program: PublicKey) =>
+  PublicKey.findProgramAddressSync(seeds, program)[0];
+
+// error is matched against the message and the program logs
+async function assertFails(tx: Promise<unknown>, error?: string) {
+  try {
+    await tx;
+  } catch (err) {
+    if (error) {
+      assert.include(`${err}\n${(err.logs ?? []).join("\n")}`, error);
+    }
+    return;
+  }
+  assert.fail("transaction succeeded");
+}
+
+describe("compressed entities", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const payer = provider.wallet.publicKey;
+
+  const instance = Math.floor(Math.random() * 1_000_000);
+  const actionBundle = Keypair.generate();
+  const merkleTree = Keypair.generate();
+  const schema = `arc://tests/compressed/${instance}`;
+
+  const registryConfig = pda([Buffer.from("registry_signer")], REGISTRY);
+  const registryInstance = pda(
+    [Buffer.from("registry"), REGISTRY.toBuffer(), u64be(instance)],
+    CORE_DS
+  );
+  const instanceAuthority = pda(
+    [Buffer.from("instance_authority"), registryInstance.toBuffer()],
+    REGISTRY
+  );
+  const registration = pda(
+    [
+      Buffer.from("action_bundle_registration"),
+      registryInstance.toBuffer(),
+      actionBundle.publicKey.toBuffer(),
+    ],
+    REGISTRY
+  );
+  const compressedTree = pda(
+    [Buffer.from("compressed_tree"), merkleTree.publicKey.toBuffer()],
+    CORE_DS
+  );
+  const component = pda([Buffer.from(schema)], REGISTRY);
+
+  // Leaves as CoreDS left them, what an indexer would rebuild from the Noop events
+  const leaves: Buffer[] = [];
+  const entity = (entityId: number, data: Buffer): Entity => ({
+    entityId,
+    instance,
+    registry: REGISTRY,
+    components: [{ key: component, maxSize: 8, data }],
+  });
+
+  const send = async (ix: TransactionInstruction, signers: Keypair[] = []) =>
+    provider.sendAndConfirm(new Transaction().add(ix), signers);
+  const registryIx = (
+    name: string,
+    keys: TransactionInstruction["keys"],
+    args: Buffer[] = []
+  ) =>
+    new TransactionInstruction({
+      programId: REGISTRY,
+      keys,
+      data: Buffer.concat([discriminator(name), ...args]),
+    });
+  const compressedAccounts = (proof: Buffer[] = []) => [
+    readonly(registryConfig),
+    readonly(registryInstance),
+    writable(compressedTree),
+    writable(merkleTree.publicKey),
+    readonly(actionBundle.publicKey, true),
+    readonly(registration),
+    readonly(CORE_DS),
+    readonly(SPL_ACCOUNT_COMPRESSION_PROGRAM_ID),
+    readonly(SPL_NOOP_PROGRAM_ID),
+    ...proof.map((node) => readonly(new PublicKey(node))),
+  ];
+
+  const createEntity = (e: Entity) =>
+    send(
+      registryIx("req_init_compressed_entity", compressedAccounts(), [
+        u64(e.entityId),
+        components(e.components),
+      ]),
+      [actionBundle]
+    );
+  const modifyEntity = (e: Entity, index: number, data: Buffer) => {
+    const { root, proof } = getProof(leaves, index);
+    return send(
+      registryIx("req_modify_compressed_entity", compressedAccounts(proof), [
+        root,
+        compressedEntity(e),
+        u32(index),
+        vec([modifyOp(component, data)]),
+      ]),
+      [actionBundle]
+    );
+  };
+  const removeEntity = (e: Entity, index: number) => {
+    const { root, proof } = getProof(leaves, index);
+    return send(
+      registryIx("req_remove_compressed_entity", compressedAccounts(proof), [
+        root,
+        compressedEntity(e),
+        u32(index),
+      ]),
+      [actionBundle]
+    );
+  };
+
+  before(async () => {
+    // The Registry may already be initalized by another test file
+    if (!(await provider.connection.getAccountInfo(registryConfig))) {
+      await send(
+        registryIx(
+          "initalize",
+          [writable(payer, true), readonly(SystemProgram.programId), writable(registryConfig)],
+          [CORE_DS.toBuffer()]
+        )
+      );
+    }
+    await send(
+      registryIx(
+        "instance_registry",
+        [
+          writable(payer, true),
+          readonly(SystemProgram.programId),
+          writable(registryConfig),
+          writable(registryInstance),
+          readonly(CORE_DS),
+          writable(instanceAuthority),
+        ],
+        [u64(instance)]
+      )
+    );
+    await send(
+      registryIx(
+        "register_component",
+        [
+          writable(payer, true),
+          readonly(SystemProgram.programId),
+          writable(component),
+          writable(registryConfig),
+        ],
+        [str(schema)]
+      )
+    );
+    const bundleAccounts = [
+      writable(payer, true),
+      readonly(SystemProgram.programId),
+      readonly(registryInstance),
+      readonly(instanceAuthority),
+      // The payer created the instance, so it's the instance authority
+      readonly(payer, true),
+      writable(registration),
+      readonly(actionBundle.publicKey),
+    ];
+    await send(registryIx("register_action_bundle", bundleAccounts));
+    await send(
+      registryIx("add_components_to_action_bundle_registration", bundleAccounts, [
+        vec([component.toBuffer()]),
+      ])
+    );
+
+    const space = getConcurrentMerkleTreeAccountSize(MAX_DEPTH, MAX_BUFFER_SIZE);
+    await send(
+      SystemProgram.createAccount({
+        fromPubkey: payer,
+        newAccountPubkey: merkleTree.publicKey,
+        lamports: await provider.connection.getMinimumBalanceForRentExemption(space),
+        space,
+        programId: SPL_ACCOUNT_COMPRESSION_PROGRAM_ID,
+      }),
+      [merkleTree]
+    );
+    await send(
+      registryIx(
+        "req_init_compressed_tree",
+        [
+          writable(payer, true),
+          readonly(SystemProgram.programId),
+          readonly(registryConfig),
+          readonly(registryInstance),
+          readonly(instanceAuthority),
+          readonly(payer, true),
+          writable(compressedTree),
+          writable(merkleTree.publicKey),
+          readonly(CORE_DS),
+          readonly(SPL_ACCOUNT_COMPRESSION_PROGRAM_ID),
+          readonly(SPL_NOOP_PROGRAM_ID),
+        ],
+        [u32(MAX_DEPTH), u32(MAX_BUFFER_SIZE)]
+      )
+    );
+  });
+
+  it("creates compressed entities", async () => {
+    for (const entityId of [0, 1]) {
+      const e = entity(entityId, Buffer.from([entityId]));
+      await createEntity(e);
+      leaves.push(keccak(compressedEntity(e)));
+    }
+  });
+
+  it("allows duplicate entity ids", async () => {
+    const e = entity(1, Buffer.from([2]));
+    await createEntity(e);
+    leaves.push(keccak(compressedEntity(e)));
+  });
+
+  it("rejects components larger than their max size", async () => {
+    await assertFails(createEntity(entity(3, Buffer.alloc(9))), "InvalidDataLengthError");
+  });
+
+  it("modifies a compressed entity with a proof", async () => {
+    const data = Buffer.from([7, 7]);
+    await modifyEntity(entity(1, Buffer.from([1])), 1, data);
+    leaves[1] = keccak(compressedEntity(entity(1, data)));
+  });
+
+  it("rejects a modify with stale entity data", async () => {
+    await assertFails(modifyEntity(entity(1, Buffer.from([1])), 1, Buffer.from([8])));
+  });
+
+  it("removes a compressed entity with a proof", async () => {
+    await removeEntity(entity(0, Buffer.from([0])), 0);
+    leaves[0] = EMPTY;
+
+    // The removed entity's leaf is gone, so it can't be modified again
+    await assertFails(modifyEntity(entity(0, Buffer.from([0])), 0, Buffer.from([1])));
+  });
+});
+
+// Creators off a Metaplex Metadata account, skipping the fields before them like Borsh reads them
+function metaplexCreators(data: Buffer) {
+  let offset = 1 + 32 + 32;
+  // name, symbol and uri
+  for (let i = 0; i < 3; i++) {
+    offset += 4 + data.readUInt32LE(offset);
+  }
+  // seller_fee_basis_points
+  offset += 2;
+  if (data[offset] === 0) {
+    return [];
+  }
+  const count = data.readUInt32LE(offset + 1);
+  offset += 5;
+  return [...Array(count).keys()].map((i) => {
+    const at = offset + i * 34;
+    return {
+      address: new PublicKey(data.subarray(at, at + 32)),
+      verified: data[at + 32] === 1,
+      share: data[at + 33],
+    };
+  });
+}
+
+describe("tsab metaplex creators", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+  const payer = provider.wallet.publicKey;
+
+  const instance = Math.floor(Math.random() * 1_000_000);
+  const entityId = 0;
+  const mint = Keypair.generate();
+  const creator = Keypair.generate();
+
+  const registryConfig = pda([Buffer.from("registry_signer")], REGISTRY);
+  const registryInstance = pda(
+    [Buffer.from("registry"), REGISTRY.toBuffer(), u64be(instance)],
+    CORE_DS
+  );
+  const instanceAuthority = pda(
+    [Buffer.from("instance_authority"), registryInstance.toBuffer()],
+    REGISTRY
+  );
+  const tsabConfig = pda([Buffer.from("tsab_signer")], TSAB);
+  const registration = pda(
+    [
+      Buffer.from("action_bundle_registration"),
+      registryInstance.toBuffer(),
+      tsabConfig.toBuffer(),
+    ],
+    REGISTRY
+  );
+  const schemas = ["metadata", "collection", "creators", "supply"].map((name) =>
+    pda([Buffer.from(`arc/tsab/${name}`)], REGISTRY)
+  );
+  const [metadataComponent, , creatorsComponent] = schemas;
+
+  const entity = pda(
+    [Buffer.from("entity"), u64be(entityId), registryInstance.toBuffer()],
+    CORE_DS
+  );
+  const arcnft = pda([Buffer.from("arcnft"), mint.publicKey.toBuffer()], CORE_DS);
+  const entityArcnft = pda([Buffer.from("entity_arcnft"), entity.toBuffer()], CORE_DS);
+  const recipientAta = pda(
+    [payer.toBuffer(), TOKEN_PROGRAM.toBuffer(), mint.publicKey.toBuffer()],
+    ASSOCIATED_TOKEN_PROGRAM
+  );
+  const metaplexMetadata = pda(
+    [Buffer.from("metadata"), TOKEN_METADATA.toBuffer(), mint.publicKey.toBuffer()],
+    TOKEN_METADATA
+  );
+  const masterEdition = pda(
+    [
+      Buffer.from("metadata"),
+      TOKEN_METADATA.toBuffer(),
+      mint.publicKey.toBuffer(),
+      Buffer.from("edition"),
+    ],
+    TOKEN_METADATA
+  );
+
+  const send = async (ix: TransactionInstruction, signers: Keypair[] = []) =>
+    provider.sendAndConfirm(new Transaction().add(ix), signers);
+  const ix = (
+    programId: PublicKey,
+    name: string,
+    keys: TransactionInstruction["keys"],
+    args: Buffer[] = []
+  ) =>
+    new TransactionInstruction({
+      programId,
+      keys,
+      data: Buffer.concat([discriminator(name), ...args]),
+    });
+  // Accounts every TSAB instruction that changes the Entity ends with
+  const entityAccounts = [
+    readonly(tsabConfig),
+    writable(entity),
+    readonly(registryConfig),
+    readonly(REGISTRY),
+    readonly(registration),
+    readonly(CORE_DS),
+    readonly(SPL_NOOP_PROGRAM_ID),
+  ];
+  const mirrorAccounts = [
+    readonly(mint.publicKey),
+    writable(metaplexMetadata),
+    readonly(TOKEN_METADATA),
+  ];
+
+  const setCreators = (creators: [PublicKey, number][]) =>
+    send(
+      ix(
+        TSAB,
+        "set_creators",
+        [
+          writable(payer, true),
+          readonly(SystemProgram.programId),
+          readonly(payer, true),
+          ...mirrorAccounts,
+          ...entityAccounts,
+        ],
+        [
+          vec(
+            creators.map(([address, share]) =>
+              Buffer.concat([address.toBuffer(), Buffer.from([share])])
+            )
+          ),
+        ]
+      )
+    );
+  const getCreators = async () =>
+    metaplexCreators((await provider.connection.getAccountInfo(metaplexMetadata)).data);
+
+  before(async () => {
+    // The Registry and TSAB may already be initalized by another test file
+    if (!(await provider.connection.getAccountInfo(registryConfig))) {
+      await send(
+        ix(
+          REGISTRY,
+          "initalize",
+          [writable(payer, true), readonly(SystemProgram.programId), writable(registryConfig)],
+          [CORE_DS.toBuffer()]
+        )
+      );
+    }
+    if (!(await provider.connection.getAccountInfo(tsabConfig))) {
+      await send(
+        ix(TSAB, "initalize", [
+          writable(payer, true),
+          readonly(SystemProgram.programId),
+          writable(tsabConfig),
+          writable(registryConfig),
+          readonly(REGISTRY),
+          ...schemas.map((schema) => writable(schema)),
+        ])
+      );
+    }
+    await send(
+      ix(
+        REGISTRY,
+        "instance_registry",
+        [
+          writable(payer, true),
+          readonly(SystemProgram.programId),
+          writable(registryConfig),
+          writable(registryInstance),
+          readonly(CORE_DS),
+          writable(instanceAuthority),
+        ],
+        [u64(instance)]
+      )
+    );
+    const bundleAccounts = [
+      writable(payer, true),
+      readonly(SystemProgram.programId),
+      readonly(registryInstance),
+      readonly(instanceAuthority),
+      readonly(payer, true),
+      writable(registration),
+      readonly(tsabConfig),
+    ];
+    await send(ix(REGISTRY, "register_action_bundle", bundleAccounts));
+    await send(
+      ix(REGISTRY, "add_components_to_action_bundle_registration", bundleAccounts, [
+        vec([metadataComponent.toBuffer(), creatorsComponent.toBuffer()]),
+      ])
+    );
+
+    // ComponentMetadata, with the payer as the update authority
+    const metadata = Buffer.concat([
+      payer.toBuffer(),
+      mint.publicKey.toBuffer(),
+      str("Creators"),
+      str("ARC"),
+      str("https://arc.test/creators.json"),
+      Buffer.from([1]),
+    ]);
+    await provider.sendAndConfirm(
+      new Transaction()
+        .add(ComputeBudgetProgram.setComputeUnitLimit({ units: 400_000 }))
+        .add(
+          ix(
+            TSAB,
+            "mint_metadata",
+            [
+              writable(payer, true),
+              readonly(SystemProgram.programId),
+              writable(mint.publicKey, true),
+              readonly(payer),
+              writable(recipientAta),
+              readonly(TOKEN_PROGRAM),
+              readonly(ASSOCIATED_TOKEN_PROGRAM),
+              writable(metaplexMetadata),
+              writable(masterEdition),
+              readonly(TOKEN_METADATA),
+              readonly(SYSVAR_RENT_PUBKEY),
+              readonly(tsabConfig),
+              writable(entity),
+              writable(arcnft),
+              writable(entityArcnft),
+              readonly(registryConfig),
+              readonly(REGISTRY),
+              readonly(registration),
+              readonly(CORE_DS),
+              readonly(SPL_NOOP_PROGRAM_ID),
+              writable(registryInstance),
+            ],
+            [u64(entityId), metadata]
+          )
+        ),
+      [mint]
+    );
+  });
+
+  it("mirrors creators with the update authority verified", async () => {
+    await setCreators([
+      [payer, 50],
+      [creator.publicKey, 50],
+    ]);
+
+    const creators = await getCreators();
+    assert.deepEqual(
+      creators.map((c) => [c.address.toBase58(), c.verified, c.share]),
+      [
+        [payer.toBase58(), true, 50],
+        [creator.publicKey.toBase58(), false, 50],
+      ]
+    );
+  });
+
+  it("verifies creators in Metaplex", async () => {
+    await send(
+      ix(TSAB, "verify_creator", [
+        readonly(creator.publicKey, true),
+        ...mirrorAccounts,
+        ...entityAccounts,
+      ]),
+      [creator]
+    );
+
+    const creators = await getCreators();
+    assert.isTrue(creators.every((c) => c.verified));
+  });
+
+  it("keeps verified creators from being removed", async () => {
+    await assertFails(setCreators([[payer, 100]]), "VerifiedCreatorRemoved");
+
+    // Shares can still change, and both stay verified
+    await setCreators([
+      [payer, 30],
+      [creator.publicKey, 70],
+    ]);
+    const creators = await getCreators();
+    assert.deepEqual(
+      creators.map((c) => [c.verified, c.share]),
+      [
+        [true, 30],
+        [true, 70],
+      ]
+    );
   });
 });